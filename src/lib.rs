@@ -3,17 +3,158 @@
 use std::collections::HashMap;
 
 use proc_macro::TokenStream;
-use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
-use quote::quote;
+use proc_macro2::{Ident, Literal, Span, TokenStream as TokenStream2};
+use quote::{quote, quote_spanned};
 use syn::{
     parse_macro_input, parse_quote, Attribute, AttributeArgs, Data, DataStruct, DeriveInput, Field,
     Fields, FieldsNamed, Lit, NestedMeta,
 };
 
+// The directives `#[boilermates(...)]` accepts on the struct, listed when an unknown one is used
+const STRUCT_DIRECTIVES: &[&str] = &[
+    "attr_for",
+    "auto_wrap",
+    "builder",
+    "cast_in",
+    "clamp_in",
+    "collect_errors",
+    "const_conversions",
+    "constructor",
+    "context_struct",
+    "count_conversions",
+    "converter_traits",
+    "cow_collection_in",
+    "deref_getters",
+    "delegate",
+    "derive_field",
+    "deserialize_any_of",
+    "diff",
+    "diff_patch",
+    "discriminant_in",
+    "doc_matrix",
+    "drop_into_flatten",
+    "dry_run",
+    "eq_source_ignoring_missing",
+    "extends",
+    "external_convert",
+    "field_attr_in",
+    "field_enum",
+    "field_order",
+    "flat_nested",
+    "free_fn_conversions",
+    "from_bytes_for",
+    "from_tuple_for",
+    "hidden_for",
+    "id_field",
+    "inline_conversions",
+    "lazy_default",
+    "into_enum_variant",
+    "into_mut",
+    "into_string_for",
+    "into_with_builder",
+    "into_with_sink",
+    "iter_convert",
+    "json_value_in",
+    "map_values_convert",
+    "method_prefix",
+    "mockable_conversions",
+    "monomorphize",
+    "nested_convert",
+    "post_convert",
+    "proto_for",
+    "provenance",
+    "proto_into",
+    "ref_variant",
+    "repr_for",
+    "revalidate_into",
+    "result_into",
+    "schema",
+    "seal_conversions",
+    "serde_default_for_boilermates_defaults",
+    "serde_with_in",
+    "split_in",
+    "stable_abi_for",
+    "strip_prefix",
+    "take_in",
+    "trace_conversions",
+    "track_caller",
+    "transform_type_in",
+    "truncate_array_in",
+    "unwrap_newtype_in",
+    "unwrap_result_in",
+    "validate_collect_into",
+    "validate_into_with_validator",
+    "validated_into",
+    "version",
+    "warn_on_drop",
+    "zip_into",
+];
+
+// The directives `#[boilermates(...)]` accepts on fields
+const FIELD_DIRECTIVES: &[&str] = &[
+    "alias_getter",
+    "cow_getter",
+    "default",
+    "fallible_getter",
+    "flatten_from",
+    "not_in",
+    "only_in",
+    "only_in_self",
+    "validate_with",
+];
+
 #[derive(Clone)]
 struct FieldConfig {
     field: Field,
     default: bool,
+    rename: Option<Ident>,
+    derived: Option<Derived>,
+    self_ref: bool,
+    // The error type of the `Result` this field was unwrapped from
+    unwrap_result: Option<syn::Type>,
+    // Whether the field has been given another numeric type, converted to with `as`
+    cast: bool,
+    // Whether values converted into this cast field saturate at its type's bounds
+    clamped: bool,
+    // Whether the field has been given another variant of its boilermates type, converted to with `into()`
+    nested: bool,
+    // Whether this field holds the value of the newtype it has in the main struct, read with `.0`
+    newtype: bool,
+    // Whether the type parameters of this field's type have been given concrete types
+    monomorphized: bool,
+    // Whether the `Vec<T>` this field holds has been turned into a `Cow<'static, [T]>`
+    cow: bool,
+    // Whether this field holds its value as a `serde_json::Value`
+    json: bool,
+    // The length the array this field holds was truncated to
+    truncated: Option<usize>,
+    // The constant value this field is always set to
+    constant: Option<syn::Expr>,
+    // Whether this is the hidden field holding the struct an instance was converted from
+    provenance: bool,
+    // The function checking this field's value in collecting validated conversions
+    validate_with: Option<syn::Expr>,
+}
+
+// How a field that's missing from a struct can still be computed from that struct's other fields
+#[derive(Clone)]
+enum Derived {
+    // Read from the field with the same name in the nested struct `parent`
+    Flatten { parent: Ident },
+    // Built by zipping the `left` and `right` collections and calling `constructor` on each pair
+    Zip { left: Ident, right: Ident, constructor: TokenStream2 },
+    // Built as a `ty` struct out of the fields with the same names
+    Nest { ty: syn::Path, fields: Vec<Ident> },
+}
+
+impl Derived {
+    fn sources(&self) -> Vec<&Ident> {
+        match self {
+            Derived::Flatten { parent } => vec![parent],
+            Derived::Zip { left, right, .. } => vec![left, right],
+            Derived::Nest { fields, .. } => fields.iter().collect(),
+        }
+    }
 }
 
 impl FieldConfig {
@@ -21,6 +162,21 @@ impl FieldConfig {
         Self {
             field,
             default,
+            rename: None,
+            derived: None,
+            self_ref: false,
+            unwrap_result: None,
+            cast: false,
+            clamped: false,
+            nested: false,
+            newtype: false,
+            monomorphized: false,
+            cow: false,
+            json: false,
+            truncated: None,
+            constant: None,
+            provenance: false,
+            validate_with: None,
         }
     }
 
@@ -28,6 +184,11 @@ impl FieldConfig {
         self.field.ident.clone().unwrap_or_else(|| panic!("Can't get field name. This should never happen."))
     }
 
+    // The field's name in the struct it's emitted in, which can differ from `name()` when renamed
+    fn ident(&self) -> Ident {
+        self.rename.clone().unwrap_or_else(|| self.name())
+    }
+
     fn trait_name(&self) -> Ident {
         Ident::new(&format!("Has{}", snake_to_pascal(&self.name().to_string())), Span::call_site())
     }
@@ -35,6 +196,101 @@ impl FieldConfig {
     fn neg_trait_name(&self) -> Ident {
         Ident::new(&format!("HasNo{}", snake_to_pascal(&self.name().to_string())), Span::call_site())
     }
+
+    // Converts the value of this field, read from `source_name`, for a struct with the same field.
+    // Fields referencing their own struct have the references converted with `Into`.
+    fn convert_value(&self, value: TokenStream2, source_name: &str) -> TokenStream2 {
+        if !self.self_ref {
+            return value;
+        }
+        convert_self_ref(&self.field.ty, value, &[source_name, "Self"]).unwrap_or_else(|| {
+            let ty = &self.field.ty;
+            panic!(
+                "Field `{}` of type `{}` references its own struct in a way that can't be converted. Only `Box`, `Option` and `Vec` are supported",
+                self.name(),
+                quote!(#ty)
+            )
+        })
+    }
+
+    // Converts the value of `other`, the field with the same name in another struct, for this field.
+    // Values of fields cast to another type are converted with `as`. When only one of the two is unwrapped from
+    // a `Result`, the value is unwrapped with `?` or wrapped in `Ok`.
+    fn convert_from(&self, other: &FieldConfig, value: TokenStream2, source_name: &str) -> TokenStream2 {
+        match self.checked_convert_from(other, value, source_name) {
+            (value, true) => quote! { #value? },
+            (value, false) => value,
+        }
+    }
+
+    // The conversion of a field, and whether it gives a `Result` that has to be checked
+    fn checked_convert_from(&self, other: &FieldConfig, value: TokenStream2, source_name: &str) -> (TokenStream2, bool) {
+        let value = other.convert_value(value, source_name);
+        if self.truncated.is_some() && self.truncated != other.truncated {
+            let value = quote! {{
+                let mut items = #value.into_iter();
+                ::std::array::from_fn(|_| items.next().unwrap_or_else(|| unreachable!()))
+            }};
+            return (value, false);
+        }
+        let (ty, other_ty) = (&self.field.ty, &other.field.ty);
+        match (self.json, other.json) {
+            (true, false) => return (quote! { ::serde_json::to_value(#value) }, true),
+            (false, true) => return (quote! { ::serde_json::from_value(#value) }, true),
+            _ => {}
+        }
+        match (self.cow, other.cow) {
+            (true, false) => return (quote! { ::std::borrow::Cow::Owned(#value) }, false),
+            (false, true) => return (quote! { (#value).into_owned() }, false),
+            _ => {}
+        }
+        match (self.newtype, other.newtype) {
+            (true, false) => return (quote! { (#value).0 }, false),
+            (false, true) => {
+                let constructor = constructor_path(ty);
+                return (quote! { #constructor(#value) }, false);
+            }
+            _ => {}
+        }
+        if (self.nested || other.nested) && quote!(#ty).to_string() != quote!(#other_ty).to_string() {
+            return (quote! { (#value).into() }, false);
+        }
+        if (self.cast || other.cast) && quote!(#ty).to_string() != quote!(#other_ty).to_string() {
+            if wraps(ty, other_ty) {
+                return (quote! { Some(#value) }, false);
+            }
+            if wraps(other_ty, ty) {
+                return (quote! { (#value).unwrap_or_default() }, false);
+            }
+            if self.clamped {
+                // Going through `i128` keeps the bounds of every integer type but `u128` representable
+                return (quote! { ((#value) as i128).clamp(#ty::MIN as i128, #ty::MAX as i128) as #ty }, false);
+            }
+            return (quote! { (#value) as #ty }, false);
+        }
+        match (&self.unwrap_result, &other.unwrap_result) {
+            (Some(_), None) => (value, true),
+            (None, Some(_)) => (quote! { Ok(#value) }, false),
+            _ => (value, false),
+        }
+    }
+
+    // A truncated array can't be converted into a longer one, a nested variant isn't converted back, and variants
+    // that give a generic field different concrete types can't convert it
+    fn can_convert_from(&self, other: &FieldConfig) -> bool {
+        if other.nested && !self.nested {
+            return false;
+        }
+        let (ty, other_ty) = (&self.field.ty, &other.field.ty);
+        if self.monomorphized && other.monomorphized && quote!(#ty).to_string() != quote!(#other_ty).to_string() {
+            return false;
+        }
+        match (self.truncated, other.truncated) {
+            (_, None) => true,
+            (None, Some(_)) => false,
+            (Some(len), Some(other_len)) => len <= other_len,
+        }
+    }
 }
 
 impl PartialEq for FieldConfig {
@@ -51,35 +307,270 @@ impl From<Field> for FieldConfig {
 
 impl From<FieldConfig> for Field {
     fn from(field_config: FieldConfig) -> Self {
-        field_config.field
+        Self {
+            ident: Some(field_config.ident()),
+            ..field_config.field
+        }
     }
 }
 
 struct Struct {
     attrs: Vec<Attribute>,
     fields: Vec<FieldConfig>,
+    // The position of each field by name, built by `index_fields` once the fields are final
+    by_name: HashMap<Ident, usize>,
 }
 
 impl Struct {
+    // Indexes the fields by name, so that matching fields across structs takes a single pass
+    fn index_fields(&mut self) {
+        self.by_name = self.fields.iter().enumerate().map(|(i, f)| (f.name(), i)).collect();
+    }
+
     fn missing_fields_from(&self, other: &Self) -> Vec<FieldConfig> {
         self.fields.iter().fold(vec![], |mut acc, field| {
-            if !other.fields.contains(field) { acc.push(field.clone()) }
+            if field.constant.is_none() && other.source_of(field).is_none() && !other.can_derive(field) {
+                acc.push(field.clone())
+            }
+            acc
+        })
+    }
+
+    // The only field of this struct, if it holds the struct `name`
+    fn envelope_of(&self, name: &Ident) -> Option<&FieldConfig> {
+        let [field] = self.fields.as_slice() else { return None };
+        matches!(&field.field.ty, syn::Type::Path(path) if path.qself.is_none() && path.path.is_ident(name)).then_some(field)
+    }
+
+    // The field with the same name as `field` that it can be converted from
+    fn source_of(&self, field: &FieldConfig) -> Option<&FieldConfig> {
+        self.field(&field.name()).filter(|f| field.can_convert_from(f))
+    }
+
+    fn field(&self, name: &Ident) -> Option<&FieldConfig> {
+        debug_assert_eq!(self.by_name.len(), self.fields.len(), "fields looked up before they're indexed");
+        self.by_name.get(name).map(|i| &self.fields[*i])
+    }
+
+    fn can_derive(&self, field: &FieldConfig) -> bool {
+        field.derived.as_ref().is_some_and(|derived| {
+            derived.sources().into_iter().all(|source| self.field(source).is_some())
+        })
+    }
+
+    // Fields that can be derived from `other`, along with the expression that computes them.
+    // `read` builds the expression that reads a field of `other` by its name in `other`.
+    fn derived_fields_from(&self, other: &Self, read: impl Fn(&Ident) -> TokenStream2) -> Vec<(FieldConfig, TokenStream2)> {
+        self.fields.iter().fold(vec![], |mut acc, field| {
+            if other.source_of(field).is_some() || !other.can_derive(field) { return acc }
+            let Some(derived) = &field.derived else { return acc };
+            let source = |name: &Ident| {
+                read(&other.field(name).unwrap_or_else(|| panic!("Can't find `{}`. This should never happen.", name)).ident())
+            };
+            let value = match derived {
+                Derived::Flatten { parent } => {
+                    let parent = source(parent);
+                    let inner = field.name();
+                    quote! { #parent.#inner }
+                }
+                Derived::Zip { left, right, constructor } => {
+                    let (left, right) = (source(left), source(right));
+                    quote! {
+                        ::std::iter::zip(#left, #right)
+                            .map(|(left, right)| #constructor(left, right))
+                            .collect()
+                    }
+                }
+                Derived::Nest { ty, fields } => {
+                    let values = fields.iter().map(source);
+                    quote! { #ty { #( #fields: #values, )* } }
+                }
+            };
+            acc.push((field.clone(), value));
             acc
         })
     }
 
-    fn same_fields_as(&self, other: &Self) -> Vec<FieldConfig> {
+    fn same_fields_as(&self, other: &Self) -> Vec<(FieldConfig, FieldConfig)> {
         self.fields.iter().fold(vec![], |mut acc, field| {
-            if other.fields.contains(field) { acc.push(field.clone()) }
+            if let Some(other_field) = other.source_of(field).filter(|_| field.constant.is_none()) {
+                acc.push((field.clone(), other_field.clone()))
+            }
             acc
         })
     }
 }
 
+struct Extends {
+    base: String,
+    add: Vec<String>,
+}
+
+#[derive(Default)]
+struct Options {
+    mockable_conversions: bool,
+    field_enum: bool,
+    inline_conversions: bool,
+    doc_matrix: bool,
+    diff: bool,
+    diff_patch: Vec<(String, String)>,
+    deref_getters: bool,
+    eq_source_ignoring_missing: bool,
+    builder: bool,
+    trace_conversions: bool,
+    provenance: bool,
+    seal_conversions: bool,
+    free_fn_conversions: bool,
+    result_into: bool,
+    context_struct: bool,
+    auto_wrap: bool,
+    const_conversions: Vec<String>,
+    constructor: bool,
+    map_values_convert: bool,
+    converter_traits: bool,
+    dry_run: bool,
+    track_caller: bool,
+    serde_default_for_boilermates_defaults: bool,
+    take_in: Vec<String>,
+    id_field: Option<String>,
+    extends: HashMap<String, Extends>,
+    strip_prefix: HashMap<String, Vec<String>>,
+    deserialize_any_of: Vec<Vec<String>>,
+    from_bytes_for: Vec<String>,
+    from_tuple_for: Vec<String>,
+    ref_variant: Vec<String>,
+    into_mut: Vec<String>,
+    collect_errors: bool,
+    stable_abi_for: Vec<StableAbi>,
+    validate_into_with_validator: Vec<String>,
+    iter_convert: bool,
+    version: Vec<(String, u32)>,
+    schema: bool,
+    proto_for: HashMap<String, Vec<(String, u32)>>,
+    zip_into: Vec<(String, String, Derived)>,
+    split_in: Vec<(String, String, Vec<String>)>,
+    field_order: HashMap<String, Vec<String>>,
+    derive_field: Vec<(String, String, String, syn::Path)>,
+    warn_on_drop: Vec<String>,
+    discriminant_in: Vec<(String, String, syn::Expr)>,
+    transform_type_in: HashMap<String, Vec<(syn::Type, syn::Expr)>>,
+    post_convert: HashMap<String, TokenStream2>,
+    into_string_for: HashMap<String, syn::Expr>,
+    delegate: Vec<(String, syn::Path)>,
+    field_attr_in: Vec<(String, String, Attribute)>,
+    serde_with_in: Vec<(String, String, String)>,
+    external_convert: Vec<(syn::Path, Vec<Ident>)>,
+    flat_nested: Vec<FlatNested>,
+    validated_into: HashMap<String, (syn::Expr, syn::Type)>,
+    validate_collect_into: Vec<String>,
+    into_with_builder: Vec<String>,
+    into_with_sink: Vec<String>,
+    monomorphize: HashMap<String, Vec<(Ident, syn::Type)>>,
+    count_conversions: Option<String>,
+    revalidate_into: HashMap<String, (syn::Expr, syn::Type)>,
+    method_prefix: String,
+    unwrap_result_in: HashMap<String, Vec<String>>,
+    cast_in: HashMap<String, Vec<(String, syn::Type)>>,
+    clamp_in: HashMap<String, Vec<String>>,
+    nested_convert: HashMap<String, Vec<(String, syn::Type)>>,
+    unwrap_newtype_in: HashMap<String, Vec<(String, syn::Type)>>,
+    cow_collection_in: HashMap<String, Vec<String>>,
+    json_value_in: HashMap<String, Vec<String>>,
+    lazy_default: Vec<(String, syn::Expr)>,
+    truncate_array_in: HashMap<String, Vec<(String, usize)>>,
+    drop_into_flatten: Option<String>,
+    proto_into: Vec<syn::Path>,
+    into_enum_variant: Vec<(syn::Path, syn::Path)>,
+}
+
+struct FlatNested {
+    flat: String,
+    nested: String,
+    field: Field,
+    inner: Vec<Ident>,
+}
+
+// The layout pinned for a struct
+struct StableAbi {
+    strukt: String,
+    offsets: Vec<(String, usize)>,
+    size: usize,
+}
+
+impl Options {
+    // The name of a generated inherent method
+    fn method_name(&self, name: &str) -> Ident {
+        Ident::new(&format!("{}{}", self.method_prefix, name), Span::call_site())
+    }
+
+    // Whether converting `source` into `target` drops a field that can only be dropped explicitly
+    fn drops_warned(&self, target: &Struct, source: &Struct) -> bool {
+        self.warn_on_drop.iter().any(|dropped| {
+            source.fields.iter().any(|f| f.name() == dropped) && !target.fields.iter().any(|f| f.name() == dropped)
+        })
+    }
+
+    // Whether a transform is registered for the type of `field` in `variant`
+    fn transforms(&self, variant: &str, field: &FieldConfig) -> bool {
+        let field_ty = &field.field.ty;
+        self.transform_type_in
+            .get(variant)
+            .into_iter()
+            .flatten()
+            .any(|(ty, _)| quote!(#ty).to_string() == quote!(#field_ty).to_string())
+    }
+
+    // Applies the transforms registered for the type of `field` to a value converted into `variant`
+    fn transform_value(&self, variant: &str, field: &FieldConfig, value: TokenStream2) -> TokenStream2 {
+        let field_ty = &field.field.ty;
+        self.transform_type_in
+            .get(variant)
+            .into_iter()
+            .flatten()
+            .filter(|(ty, _)| quote!(#ty).to_string() == quote!(#field_ty).to_string())
+            .fold(value, |value, (_, transform)| quote! { (#transform)(#value) })
+    }
+
+    fn rename_in(&self, variant: &str, field: &Ident) -> Option<Ident> {
+        let name = field.to_string();
+        self.strip_prefix
+            .get(variant)?
+            .iter()
+            .find_map(|prefix| name.strip_prefix(prefix.as_str()))
+            .map(|stripped| Ident::new(stripped, Span::call_site()))
+    }
+
+    fn root_of<'a>(&'a self, mut variant: &'a str) -> &'a str {
+        while let Some(extends) = self.extends.get(variant) {
+            variant = &extends.base;
+        }
+        variant
+    }
+
+    // A variant that extends another one has the base's fields plus the added ones. The root of a
+    // chain doesn't get the fields that are added further down the chain, unless it's the main struct.
+    fn extends_contains(&self, variant: &str, field: &str, add_to: &[String], main: &str) -> bool {
+        match self.extends.get(variant) {
+            Some(extends) => {
+                extends.add.iter().any(|a| a == field)
+                    || self.extends_contains(&extends.base, field, add_to, main)
+            }
+            None => {
+                add_to.iter().any(|s| s == variant)
+                    && (variant == main
+                        || !self.extends.iter().any(|(v, e)| {
+                            e.add.iter().any(|a| a == field) && self.root_of(v) == variant
+                        }))
+            }
+        }
+    }
+}
+
 #[proc_macro_attribute]
 pub fn boilermates(attr: TokenStream, item: TokenStream) -> TokenStream {
     // let mut new_structs = Structs::new();
     let mut structs = HashMap::<String, Struct>::new();
+    let mut options = Options::default();
 
     // Parse the input item
     let mut main = parse_macro_input!(item as DeriveInput);
@@ -93,6 +584,12 @@ pub fn boilermates(attr: TokenStream, item: TokenStream) -> TokenStream {
         panic!("Expected a struct with named fields");
     };
 
+    // Variants are emitted without generic parameters, so only type parameters, which can be substituted, are supported
+    if main.generics.lifetimes().next().is_some() || main.generics.const_params().next().is_some() {
+        panic!("`{}` can only have type parameters", main.ident);
+    }
+    let generic_params = main.generics.type_params().map(|param| param.ident.clone()).collect::<Vec<_>>();
+
     // Inline module name
     // let module_name = Ident::new(&format!("boilermates{}", pascal_to_snake(&main.ident.to_string())), Span::call_site());
 
@@ -108,6 +605,7 @@ pub fn boilermates(attr: TokenStream, item: TokenStream) -> TokenStream {
                     Struct {
                         attrs: vec![],
                         fields: vec![],
+                        by_name: HashMap::new(),
                     },
                 );
             }
@@ -119,6 +617,8 @@ pub fn boilermates(attr: TokenStream, item: TokenStream) -> TokenStream {
     // let mut reexport = false;
     // let mut use_in_place = false;
 
+    let main_name = main.ident.to_string();
+
     // Check if attributes are of the following format "#[boilermates(attr_for({x}, {y}))]"
     // and extract {x} and {y}
     main.attrs.retain(|attr| {
@@ -132,6 +632,62 @@ pub fn boilermates(attr: TokenStream, item: TokenStream) -> TokenStream {
             Some(syn::NestedMeta::Meta(syn::Meta::List(nv))) => {
                 let Some(ident) = nv.path.get_ident() else { return true };
                 match ident.to_string().as_str() {
+                    "field_attr_in" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                        nv.nested.iter().nth(2),
+                    ) {
+                        (
+                            3,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(field))),
+                            Some(NestedMeta::Lit(Lit::Str(attr_lit))),
+                        ) => {
+                            let strukt = strukt.value();
+                            if !structs.contains_key(&strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            let attr_tokens: TokenStream2 = attr_lit
+                                .value()
+                                .parse()
+                                .unwrap_or_else(|e| panic!("Could not parse attribute: {}", e));
+                            let attr = syn::parse::Parser::parse2(Attribute::parse_outer, attr_tokens)
+                                .ok()
+                                .and_then(|attrs| attrs.into_iter().next())
+                                .unwrap_or_else(|| panic!("Could not parse attribute `{}`", attr_lit.value()));
+                            options.field_attr_in.push((strukt, field.value(), attr));
+                        }
+                        _ => panic!(
+                            "`#[boilermates(field_attr_in(...))]` must have three string literal arguments"
+                        ),
+                    },
+                    "serde_with_in" => {
+                        if !cfg!(feature = "serde") {
+                            panic!("`#[boilermates(serde_with_in(...))]` requires the `serde` feature");
+                        }
+                        let args = extract_nested_list(nv);
+                        let [strukt, field, module] = args.as_slice() else {
+                            panic!("`#[boilermates(serde_with_in(...))]` must have the form `serde_with_in(\"Struct\", \"field\", \"module\")`");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        options.serde_with_in.push((strukt.clone(), field.clone(), module.clone()));
+                    }
+                    "hidden_for" => {
+                        let hidden_for = extract_nested_list(nv);
+                        if hidden_for.is_empty() {
+                            panic!("`#[boilermates(hidden_for(...))]` must have at least one argument");
+                        }
+                        hidden_for.iter().for_each(|strukt| {
+                            structs
+                                .get_mut(strukt)
+                                .unwrap_or_else(|| panic!("Struct `{}` not declared", strukt))
+                                .attrs
+                                .push(parse_quote!(#[doc(hidden)]));
+                        });
+                    }
                     "attr_for" => match (
                         nv.nested.len(),
                         nv.nested.iter().next(),
@@ -159,299 +715,3846 @@ pub fn boilermates(attr: TokenStream, item: TokenStream) -> TokenStream {
                             "`#[boilermates(attr_for(...))]` must have two string literal arguments"
                         ),
                     },
-                    _ => panic!("Unknown attrbute `#[boilermates({})]`", ident),
-                }
-            }
-
-            // Some(syn::NestedMeta::Meta(syn::Meta::Path(path))) => {
-            //     let Some(ident) = path.get_ident() else { return true };
-            //     match ident.to_string().as_str() {
-            //         "reexport" => reexport = true,
-            //         "use_in_place" => use_in_place = true,
-            //         _ => panic!("Unknown attrbute `#[boilermates({})]`", ident),
-            //     }
-            // }
-
-            _ => return true,
-        }
-        false
-    });
-
-    fn extract_nested_list(meta_list: &syn::MetaList) -> Vec<String> {
-        meta_list
-            .nested
-            .iter()
-            .map(|n| match n {
-                NestedMeta::Lit(Lit::Str(lit)) => lit.value().trim_matches('"').to_owned(),
-                _ => panic!("Expected a string literal"),
-            })
-            .collect()
-    }
-
-    structs.insert(
-        main.ident.to_string(),
-        Struct {
-            attrs: main.attrs.clone(),
-            fields: vec![],
-        },
-    );
-
-    let mut traits = quote! {};
-
-    fields.named.iter_mut().for_each(|field| {
-        let mut add_to = structs.keys().cloned().collect::<Vec<_>>();
-        let mut default = false;
-        field.attrs.retain(|attr| {
-            let Ok(meta) = attr.parse_meta() else { return true };
-            let syn::Meta::List(list) = meta  else { return true };
-            let Some(name) = list.path.get_ident() else { return true };
-            if name != "boilermates" { return true }
-            match list.nested.first() {
-                Some(syn::NestedMeta::Meta(syn::Meta::List(nv))) => {
-                    let Some(ident) = nv.path.get_ident() else { panic!("#[boilermates] parsing error") };
-                    let ident = ident.to_string();
-                    if ident == "only_in" {
-                        let nested = extract_nested_list(nv);
-                        if nested.is_empty() {
-                            panic!(
-                                "`#[boilermates(only_in(...))]` must have at least one argument"
-                            );
+                    "repr_for" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                    ) {
+                        (
+                            2,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(repr))),
+                        ) => {
+                            let repr = repr.value().trim_matches('"').to_owned();
+                            if !["C", "transparent", "packed", "Rust"].contains(&repr.as_str()) {
+                                panic!("Unknown repr `{}`, expected one of `C`, `transparent`, `packed` or `Rust`", repr);
+                            }
+                            let repr = Ident::new(&repr, Span::call_site());
+                            structs
+                                .get_mut(strukt.value().trim_matches('"'))
+                                .unwrap_or_else(|| panic!("Struct `{}` not declared", strukt.value()))
+                                .attrs
+                                .push(parse_quote!(#[repr(#repr)]));
                         }
-                        nested.iter().for_each(|n| {
-                            if !add_to.iter().any(|s| s == n.as_str()) {
-                                panic!(
-                                    "`#[boilermates(only_in(...))]` has undeclared struct name `{}`",
-                                    n
-                                );
+                        _ => panic!(
+                            "`#[boilermates(repr_for(...))]` must have two string literal arguments"
+                        ),
+                    },
+                    "extends" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                        nv.nested.iter().nth(2),
+                    ) {
+                        (
+                            3,
+                            Some(NestedMeta::Lit(Lit::Str(variant))),
+                            Some(NestedMeta::Lit(Lit::Str(base))),
+                            Some(NestedMeta::Meta(syn::Meta::List(add))),
+                        ) if add.path.is_ident("add") => {
+                            let variant = variant.value().trim_matches('"').to_owned();
+                            let base = base.value().trim_matches('"').to_owned();
+                            if variant == main_name {
+                                panic!("`#[boilermates(extends(...))]` can't be used to extend `{}` itself", main_name);
+                            }
+                            if !structs.contains_key(&variant) {
+                                panic!("Struct `{}` not declared", variant);
+                            }
+                            if base != main_name && !structs.contains_key(&base) {
+                                panic!("Base struct `{}` of `{}` not declared", base, variant);
+                            }
+                            let add = extract_nested_list(add);
+                            if options.extends.insert(variant.clone(), Extends { base, add }).is_some() {
+                                panic!("`{}` can only extend one struct", variant);
                             }
-                        });
-                        add_to.retain(|s| nested.iter().any(|n| s == n.as_str()));
-                    } else if ident == "not_in" {
-                        let nested = extract_nested_list(nv);
-                        if nested.is_empty() {
-                            panic!(
-                                "`#[boilermates(only_in(...))]` must have at least one argument"
-                            );
                         }
-                        nested.iter().for_each(|n| {
-                            if !add_to.iter().any(|s| s == n.as_str()) {
-                                panic!(
-                                    "`#[boilermates(only_in(...))]` has undeclared struct name `{}`",
-                                    n
-                                );
+                        _ => panic!(
+                            "`#[boilermates(extends(...))]` must have the form `extends(\"Variant\", \"Base\", add(\"field\", ...))`"
+                        ),
+                    },
+                    "strip_prefix" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                    ) {
+                        (
+                            2,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(prefix))),
+                        ) => {
+                            let strukt = strukt.value().trim_matches('"').to_owned();
+                            if !structs.contains_key(&strukt) {
+                                panic!("Struct `{}` not declared", strukt);
                             }
-                        });
-                        add_to.retain(|s| !nested.iter().any(|n| s == n.as_str()));
-                    } else {
-                        panic!("Unknown attrbute `#[boilermates({})]`", ident);
+                            options
+                                .strip_prefix
+                                .entry(strukt)
+                                .or_default()
+                                .push(prefix.value().trim_matches('"').to_owned());
+                        }
+                        _ => panic!(
+                            "`#[boilermates(strip_prefix(...))]` must have two string literal arguments"
+                        ),
+                    },
+                    "drop_into_flatten" => match (nv.nested.len(), nv.nested.first()) {
+                        (1, Some(NestedMeta::Lit(Lit::Str(flatten)))) => {
+                            if !cfg!(feature = "serde") {
+                                panic!("`#[boilermates(drop_into_flatten(...))]` requires the `serde` feature");
+                            }
+                            options.drop_into_flatten = Some(flatten.value().trim_matches('"').to_owned());
+                        }
+                        _ => panic!("`#[boilermates(drop_into_flatten(...))]` must have one string literal argument"),
+                    },
+                    "method_prefix" => match (nv.nested.len(), nv.nested.first()) {
+                        (1, Some(NestedMeta::Lit(Lit::Str(prefix)))) => {
+                            options.method_prefix = prefix.value().trim_matches('"').to_owned();
+                        }
+                        _ => panic!("`#[boilermates(method_prefix(...))]` must have one string literal argument"),
+                    },
+                    "id_field" => match (nv.nested.len(), nv.nested.first()) {
+                        (1, Some(NestedMeta::Lit(Lit::Str(id_field)))) => {
+                            options.id_field = Some(id_field.value().trim_matches('"').to_owned());
+                        }
+                        _ => panic!("`#[boilermates(id_field(...))]` must have one string literal argument"),
+                    },
+                    "warn_on_drop" => {
+                        let warn_on_drop = extract_nested_list(nv);
+                        if warn_on_drop.is_empty() {
+                            panic!("`#[boilermates(warn_on_drop(...))]` must have at least one argument");
+                        }
+                        options.warn_on_drop.extend(warn_on_drop);
                     }
-                }
-
-                Some(syn::NestedMeta::Meta(syn::Meta::Path(path))) => {
-                    let Some(ident) = path.get_ident() else { panic!("#[boilermates] parsing error") };
-                    match ident.to_string().as_str() {
-                        "default" => default = true,
-                        "only_in_self" => add_to = vec![main.ident.to_string()],
-                        _ => panic!("Unknown attrbute `#[boilermates({})]`", ident),
+                    "take_in" => {
+                        let take_in = extract_nested_list(nv);
+                        if take_in.is_empty() {
+                            panic!("`#[boilermates(take_in(...))]` must have at least one argument");
+                        }
+                        options.take_in.extend(take_in);
                     }
-                }
-
-                _ => return true,
-            }
-            
-            false
-        });
-
-        let field = FieldConfig::new(field.clone(), default);
-        let trait_name = field.trait_name();
-        let neg_trait_name = field.neg_trait_name();
-        let field_name = field.name();
-        let setter_fn = Ident::new(&format!("set_{}", field_name), Span::call_site());
-        let field_ty = &field.field.ty;
-        traits = quote! {
-            #traits
-            trait #trait_name {
-                fn #field_name(&self) -> &#field_ty;
-                fn #setter_fn(&mut self, value: #field_ty);
+                    "proto_for" => {
+                        if !cfg!(feature = "prost") {
+                            panic!("`#[boilermates(proto_for(...))]` requires the `prost` feature");
+                        }
+                        let mut nested = nv.nested.iter();
+                        let Some(NestedMeta::Lit(Lit::Str(strukt))) = nested.next() else {
+                            panic!("`#[boilermates(proto_for(...))]` must start with a struct name");
+                        };
+                        let strukt = strukt.value().trim_matches('"').to_owned();
+                        if !structs.contains_key(&strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let numbers = nested.map(|n| match n {
+                            NestedMeta::Meta(syn::Meta::List(number)) if number.path.is_ident("field_number") => {
+                                match (number.nested.len(), number.nested.first(), number.nested.iter().nth(1)) {
+                                    (
+                                        2,
+                                        Some(NestedMeta::Lit(Lit::Str(field))),
+                                        Some(NestedMeta::Lit(Lit::Int(tag))),
+                                    ) => (
+                                        field.value().trim_matches('"').to_owned(),
+                                        tag.base10_parse::<u32>().unwrap_or_else(|e| panic!("Invalid field number: {}", e)),
+                                    ),
+                                    _ => panic!("`field_number(...)` must have a field name and a number"),
+                                }
+                            }
+                            _ => panic!("`#[boilermates(proto_for(...))]` expects `field_number(...)` arguments"),
+                        }).collect::<Vec<_>>();
+                        numbers.iter().enumerate().for_each(|(i, (field, tag))| {
+                            if numbers[..i].iter().any(|(_, t)| t == tag) {
+                                panic!("Field number {} of `{}` is used more than once", tag, field);
+                            }
+                        });
+                        options.proto_for.insert(strukt, numbers);
+                    }
+                    "post_convert" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                    ) {
+                        (
+                            2,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(hook))),
+                        ) => {
+                            let strukt = strukt.value().trim_matches('"').to_owned();
+                            if !structs.contains_key(&strukt) && strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            let hook = hook
+                                .parse::<syn::Expr>()
+                                .unwrap_or_else(|e| panic!("Could not parse hook: {}", e));
+                            options.post_convert.insert(strukt, quote! { #hook });
+                        }
+                        _ => panic!(
+                            "`#[boilermates(post_convert(...))]` must have two string literal arguments"
+                        ),
+                    },
+                    "delegate" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                    ) {
+                        (
+                            2,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(trait_path))),
+                        ) => {
+                            let strukt = strukt.value().trim_matches('"').to_owned();
+                            if !structs.contains_key(&strukt) && strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            let trait_path = trait_path
+                                .parse::<syn::Path>()
+                                .unwrap_or_else(|e| panic!("Could not parse trait: {}", e));
+                            options.delegate.push((strukt, trait_path));
+                        }
+                        _ => panic!(
+                            "`#[boilermates(delegate(...))]` must have two string literal arguments"
+                        ),
+                    },
+                    "into_string_for" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                    ) {
+                        (
+                            2,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(expr))),
+                        ) => {
+                            let strukt = strukt.value().trim_matches('"').to_owned();
+                            if !structs.contains_key(&strukt) && strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            let expr = expr
+                                .parse::<syn::Expr>()
+                                .unwrap_or_else(|e| panic!("Could not parse expression: {}", e));
+                            options.into_string_for.insert(strukt, expr);
+                        }
+                        _ => panic!(
+                            "`#[boilermates(into_string_for(...))]` must have two string literal arguments"
+                        ),
+                    },
+                    "flat_nested" => {
+                        let usage = "`#[boilermates(flat_nested(...))]` must have the form `flat_nested(\"Flat\", \"Nested\", group(\"field: Type\", \"inner\", ...))`";
+                        let (
+                            3,
+                            Some(NestedMeta::Lit(Lit::Str(flat))),
+                            Some(NestedMeta::Lit(Lit::Str(nested))),
+                            Some(NestedMeta::Meta(syn::Meta::List(group))),
+                        ) = (
+                            nv.nested.len(),
+                            nv.nested.iter().next(),
+                            nv.nested.iter().nth(1),
+                            nv.nested.iter().nth(2),
+                        ) else {
+                            panic!("{}", usage)
+                        };
+                        if !group.path.is_ident("group") {
+                            panic!("{}", usage)
+                        }
+                        let (flat, nested) = (flat.value().trim_matches('"').to_owned(), nested.value().trim_matches('"').to_owned());
+                        [&flat, &nested].into_iter().for_each(|s| {
+                            if !structs.contains_key(s) && *s != main_name {
+                                panic!("Struct `{}` not declared", s);
+                            }
+                        });
+                        let mut group = extract_nested_list(group).into_iter();
+                        let group_field = group.next().unwrap_or_default();
+                        let Some((field, ty)) = group_field.split_once(':') else {
+                            panic!("{}", usage)
+                        };
+                        let ty = syn::parse_str::<syn::Type>(ty)
+                            .unwrap_or_else(|e| panic!("Could not parse type: {}", e));
+                        let inner = group.map(|f| Ident::new(&f, Span::call_site())).collect::<Vec<_>>();
+                        if inner.is_empty() {
+                            panic!("{}", usage)
+                        }
+                        options.flat_nested.push(FlatNested {
+                            flat,
+                            nested,
+                            field: Field {
+                                attrs: vec![],
+                                vis: main.vis.clone(),
+                                ident: Some(Ident::new(field.trim(), Span::call_site())),
+                                colon_token: Some(Default::default()),
+                                ty,
+                            },
+                            inner,
+                        });
+                    }
+                    "validated_into" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, validate, error] = args.as_slice() else {
+                            panic!("`#[boilermates(validated_into(...))]` must have the form `validated_into(\"Struct\", \"validate_fn\", \"ErrorType\")`");
+                        };
+                        if !structs.contains_key(strukt) && *strukt != main_name {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let validate = syn::parse_str::<syn::Expr>(validate)
+                            .unwrap_or_else(|e| panic!("Could not parse validation function: {}", e));
+                        let error = syn::parse_str::<syn::Type>(error)
+                            .unwrap_or_else(|e| panic!("Could not parse error type: {}", e));
+                        if options.validate_collect_into.contains(strukt) {
+                            panic!("`{}` can't be in both `validated_into` and `validate_collect_into`", strukt);
+                        }
+                        options.validated_into.insert(strukt.clone(), (validate, error));
+                    }
+                    "revalidate_into" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, revalidate, error] = args.as_slice() else {
+                            panic!("`#[boilermates(revalidate_into(...))]` must have the form `revalidate_into(\"Struct\", \"revalidate_fn\", \"ErrorType\")`");
+                        };
+                        if !structs.contains_key(strukt) && *strukt != main_name {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let revalidate = syn::parse_str::<syn::Expr>(revalidate)
+                            .unwrap_or_else(|e| panic!("Could not parse revalidation function: {}", e));
+                        let error = syn::parse_str::<syn::Type>(error)
+                            .unwrap_or_else(|e| panic!("Could not parse error type: {}", e));
+                        options.revalidate_into.insert(strukt.clone(), (revalidate, error));
+                    }
+                    "count_conversions" => match (nv.nested.len(), nv.nested.first()) {
+                        (1, Some(NestedMeta::Lit(Lit::Str(metric)))) => {
+                            options.count_conversions = Some(metric.value());
+                        }
+                        _ => panic!("`#[boilermates(count_conversions(...))]` must have one string literal argument"),
+                    },
+                    "into_with_builder" => {
+                        let into_with_builder = extract_nested_list(nv);
+                        if into_with_builder.is_empty() {
+                            panic!("`#[boilermates(into_with_builder(...))]` must have at least one argument");
+                        }
+                        into_with_builder.iter().for_each(|strukt| {
+                            if !structs.contains_key(strukt) && *strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                        });
+                        options.into_with_builder.extend(into_with_builder);
+                    }
+                    "const_conversions" => {
+                        let const_conversions = extract_nested_list(nv);
+                        if const_conversions.is_empty() {
+                            panic!("`#[boilermates(const_conversions(...))]` must have at least one argument");
+                        }
+                        const_conversions.iter().for_each(|strukt| {
+                            if !structs.contains_key(strukt) && *strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                        });
+                        options.const_conversions.extend(const_conversions);
+                    }
+                    "into_with_sink" => {
+                        let into_with_sink = extract_nested_list(nv);
+                        if into_with_sink.is_empty() {
+                            panic!("`#[boilermates(into_with_sink(...))]` must have at least one argument");
+                        }
+                        into_with_sink.iter().for_each(|strukt| {
+                            if !structs.contains_key(strukt) && *strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                        });
+                        options.into_with_sink.extend(into_with_sink);
+                    }
+                    "validate_collect_into" => {
+                        let validate_collect_into = extract_nested_list(nv);
+                        if validate_collect_into.is_empty() {
+                            panic!("`#[boilermates(validate_collect_into(...))]` must have at least one argument");
+                        }
+                        validate_collect_into.iter().for_each(|strukt| {
+                            if !structs.contains_key(strukt) && *strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            if options.validated_into.contains_key(strukt) {
+                                panic!("`{}` can't be in both `validated_into` and `validate_collect_into`", strukt);
+                            }
+                        });
+                        options.validate_collect_into.extend(validate_collect_into);
+                    }
+                    "external_convert" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                    ) {
+                        (
+                            2,
+                            Some(NestedMeta::Lit(Lit::Str(external))),
+                            Some(NestedMeta::Meta(syn::Meta::List(external_fields))),
+                        ) if external_fields.path.is_ident("fields") => {
+                            let external = syn::parse_str::<syn::Path>(&external.value())
+                                .unwrap_or_else(|e| panic!("Could not parse external type path: {}", e));
+                            let external_fields = external_fields.nested.iter().map(|field| match field {
+                                NestedMeta::Meta(syn::Meta::Path(path)) => path
+                                    .get_ident()
+                                    .cloned()
+                                    .unwrap_or_else(|| panic!("`fields(...)` arguments must be field names")),
+                                _ => panic!("`fields(...)` arguments must be field names"),
+                            }).collect::<Vec<_>>();
+                            if external_fields.is_empty() {
+                                panic!("`fields(...)` must have at least one field");
+                            }
+                            options.external_convert.push((external, external_fields));
+                        }
+                        _ => panic!(
+                            "`#[boilermates(external_convert(...))]` must have the form `external_convert(\"path::Type\", fields(field, ...))`"
+                        ),
+                    },
+                    "proto_into" => match (nv.nested.len(), nv.nested.first()) {
+                        (1, Some(NestedMeta::Lit(Lit::Str(proto)))) => {
+                            let proto = syn::parse_str::<syn::Path>(proto.value().trim_matches('"'))
+                                .unwrap_or_else(|e| panic!("Could not parse proto struct path: {}", e));
+                            options.proto_into.push(proto);
+                        }
+                        _ => panic!("`#[boilermates(proto_into(...))]` must have one string literal argument"),
+                    },
+                    "unwrap_result_in" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, unwrapped @ ..] = args.as_slice() else {
+                            panic!("`#[boilermates(unwrap_result_in(...))]` must start with a struct name");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        if unwrapped.is_empty() {
+                            panic!("`#[boilermates(unwrap_result_in(...))]` must have at least one field");
+                        }
+                        options.unwrap_result_in.entry(strukt.clone()).or_default().extend_from_slice(unwrapped);
+                    }
+                    "cast_in" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, field, ty] = args.as_slice() else {
+                            panic!("`#[boilermates(cast_in(...))]` must have the form `cast_in(\"Struct\", \"field\", \"Type\")`");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let ty = syn::parse_str::<syn::Type>(ty)
+                            .unwrap_or_else(|e| panic!("Could not parse type: {}", e));
+                        options.cast_in.entry(strukt.clone()).or_default().push((field.clone(), ty));
+                    }
+                    "lazy_default" => {
+                        let args = extract_nested_list(nv);
+                        let [field, init] = args.as_slice() else {
+                            panic!("`#[boilermates(lazy_default(...))]` must have the form `lazy_default(\"field\", \"init\")`");
+                        };
+                        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == field)) {
+                            panic!("`#[boilermates(lazy_default(...))]` has unknown field `{}`", field);
+                        }
+                        let init = syn::parse_str::<syn::Expr>(init)
+                            .unwrap_or_else(|e| panic!("Could not parse initializer: {}", e));
+                        options.lazy_default.push((field.clone(), init));
+                    }
+                    "json_value_in" => {
+                        if !cfg!(feature = "serde") {
+                            panic!("`#[boilermates(json_value_in(...))]` requires the `serde` feature");
+                        }
+                        let args = extract_nested_list(nv);
+                        let [strukt, json @ ..] = args.as_slice() else {
+                            panic!("`#[boilermates(json_value_in(...))]` must start with a struct name");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        if json.is_empty() {
+                            panic!("`#[boilermates(json_value_in(...))]` must have at least one field");
+                        }
+                        json.iter().for_each(|json| {
+                            if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == json)) {
+                                panic!("`#[boilermates(json_value_in(...))]` has unknown field `{}`", json);
+                            }
+                        });
+                        options.json_value_in.entry(strukt.clone()).or_default().extend_from_slice(json);
+                    }
+                    "cow_collection_in" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, cows @ ..] = args.as_slice() else {
+                            panic!("`#[boilermates(cow_collection_in(...))]` must start with a struct name");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        if cows.is_empty() {
+                            panic!("`#[boilermates(cow_collection_in(...))]` must have at least one field");
+                        }
+                        options.cow_collection_in.entry(strukt.clone()).or_default().extend_from_slice(cows);
+                    }
+                    "clamp_in" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, clamped @ ..] = args.as_slice() else {
+                            panic!("`#[boilermates(clamp_in(...))]` must start with a struct name");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        if clamped.is_empty() {
+                            panic!("`#[boilermates(clamp_in(...))]` must have at least one field");
+                        }
+                        options.clamp_in.entry(strukt.clone()).or_default().extend_from_slice(clamped);
+                    }
+                    "nested_convert" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, field, ty] = args.as_slice() else {
+                            panic!("`#[boilermates(nested_convert(...))]` must have the form `nested_convert(\"Struct\", \"field\", \"Type\")`");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let ty = syn::parse_str::<syn::Type>(ty)
+                            .unwrap_or_else(|e| panic!("Could not parse type: {}", e));
+                        options.nested_convert.entry(strukt.clone()).or_default().push((field.clone(), ty));
+                    }
+                    "monomorphize" => {
+                        let mut args = nv.nested.iter();
+                        let Some(NestedMeta::Lit(Lit::Str(strukt))) = args.next() else {
+                            panic!("`#[boilermates(monomorphize(...))]` must have the form `monomorphize(\"Struct\", T = \"Type\", ...)`");
+                        };
+                        let strukt = strukt.value();
+                        if !structs.contains_key(&strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let params = args.map(|arg| {
+                            let NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, lit: Lit::Str(ty), .. })) = arg else {
+                                panic!("`#[boilermates(monomorphize(...))]` must have the form `monomorphize(\"Struct\", T = \"Type\", ...)`");
+                            };
+                            let param = path.get_ident().cloned().unwrap_or_else(|| panic!("Expected a type parameter name"));
+                            let ty = ty.parse::<syn::Type>().unwrap_or_else(|e| panic!("Could not parse type: {}", e));
+                            (param, ty)
+                        }).collect::<Vec<_>>();
+                        options.monomorphize.insert(strukt, params);
+                    }
+                    "unwrap_newtype_in" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, field, ty] = args.as_slice() else {
+                            panic!("`#[boilermates(unwrap_newtype_in(...))]` must have the form `unwrap_newtype_in(\"Struct\", \"field\", \"Type\")`");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let ty = syn::parse_str::<syn::Type>(ty)
+                            .unwrap_or_else(|e| panic!("Could not parse type: {}", e));
+                        options.unwrap_newtype_in.entry(strukt.clone()).or_default().push((field.clone(), ty));
+                    }
+                    "truncate_array_in" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                        nv.nested.iter().nth(2),
+                    ) {
+                        (
+                            3,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(field))),
+                            Some(NestedMeta::Lit(Lit::Int(len))),
+                        ) => {
+                            let strukt = strukt.value().trim_matches('"').to_owned();
+                            if !structs.contains_key(&strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            let len = len.base10_parse::<usize>().unwrap_or_else(|e| panic!("Invalid array length: {}", e));
+                            options
+                                .truncate_array_in
+                                .entry(strukt)
+                                .or_default()
+                                .push((field.value().trim_matches('"').to_owned(), len));
+                        }
+                        _ => panic!(
+                            "`#[boilermates(truncate_array_in(...))]` must have the form `truncate_array_in(\"Struct\", \"field\", length)`"
+                        ),
+                    },
+                    "version" => match (nv.nested.len(), nv.nested.iter().next(), nv.nested.iter().nth(1)) {
+                        (2, Some(NestedMeta::Lit(Lit::Str(strukt))), Some(NestedMeta::Lit(Lit::Int(version)))) => {
+                            let strukt = strukt.value().trim_matches('"').to_owned();
+                            if strukt != main_name && !structs.contains_key(&strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            let version = version.base10_parse::<u32>().unwrap_or_else(|e| panic!("Invalid version: {}", e));
+                            if options.version.iter().any(|(s, _)| *s == strukt) {
+                                panic!("`#[boilermates(version(...))]` is set more than once for `{}`", strukt);
+                            }
+                            if let Some((other, _)) = options.version.iter().find(|(_, v)| *v == version) {
+                                panic!("`{}` and `{}` can't both be version {}", other, strukt, version);
+                            }
+                            options.version.push((strukt, version));
+                        }
+                        _ => panic!("`#[boilermates(version(...))]` must have the form `version(\"Struct\", version)`"),
+                    },
+                    "into_enum_variant" => {
+                        let args = extract_nested_list(nv);
+                        let [enum_path, variant_path] = args.as_slice() else {
+                            panic!("`#[boilermates(into_enum_variant(...))]` must have two string literal arguments");
+                        };
+                        let parse_path = |path: &str| syn::parse_str::<syn::Path>(path)
+                            .unwrap_or_else(|e| panic!("Could not parse path `{}`: {}", path, e));
+                        options.into_enum_variant.push((parse_path(enum_path), parse_path(variant_path)));
+                    }
+                    "zip_into" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, field, left, right, constructor] = args.as_slice() else {
+                            panic!("`#[boilermates(zip_into(...))]` must have the form `zip_into(\"Struct\", \"field\", \"left\", \"right\", \"constructor\")`");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let constructor = constructor
+                            .parse::<TokenStream2>()
+                            .ok()
+                            .filter(|tokens| syn::parse2::<syn::Expr>(tokens.clone()).is_ok())
+                            .unwrap_or_else(|| panic!("Could not parse constructor `{}`", constructor));
+                        options.zip_into.push((strukt.clone(), field.clone(), Derived::Zip {
+                            left: Ident::new(left, Span::call_site()),
+                            right: Ident::new(right, Span::call_site()),
+                            constructor,
+                        }));
+                    }
+                    "transform_type_in" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, ty, transform] = args.as_slice() else {
+                            panic!("`#[boilermates(transform_type_in(...))]` must have the form `transform_type_in(\"Struct\", \"Type\", \"transform\")`");
+                        };
+                        if !structs.contains_key(strukt) && *strukt != main_name {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let ty = syn::parse_str::<syn::Type>(ty)
+                            .unwrap_or_else(|e| panic!("Could not parse type: {}", e));
+                        let transform = syn::parse_str::<syn::Expr>(transform)
+                            .unwrap_or_else(|e| panic!("Could not parse transform: {}", e));
+                        options.transform_type_in.entry(strukt.clone()).or_default().push((ty, transform));
+                    }
+                    "discriminant_in" => match (
+                        nv.nested.len(),
+                        nv.nested.iter().next(),
+                        nv.nested.iter().nth(1),
+                        nv.nested.iter().nth(2),
+                    ) {
+                        (
+                            3,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Lit(Lit::Str(field))),
+                            Some(NestedMeta::Lit(Lit::Str(value))),
+                        ) => {
+                            let strukt = strukt.value().trim_matches('"').to_owned();
+                            if !structs.contains_key(&strukt) && strukt != main_name {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            // The value isn't trimmed, as it's often a string literal itself
+                            let value = value
+                                .parse::<syn::Expr>()
+                                .unwrap_or_else(|e| panic!("Could not parse discriminant value: {}", e));
+                            options.discriminant_in.push((strukt, field.value().trim_matches('"').to_owned(), value));
+                        }
+                        _ => panic!(
+                            "`#[boilermates(discriminant_in(...))]` must have three string literal arguments"
+                        ),
+                    },
+                    "derive_field" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, field, function] = args.as_slice() else {
+                            panic!("`#[boilermates(derive_field(...))]` must have the form `derive_field(\"Struct\", \"field\", \"Source::function\")`");
+                        };
+                        if !structs.contains_key(strukt) && *strukt != main_name {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let function = syn::parse_str::<syn::Path>(function)
+                            .unwrap_or_else(|e| panic!("Could not parse function path: {}", e));
+                        // The type the function is defined on is the source it computes the field from
+                        let source = function
+                            .segments
+                            .iter()
+                            .rev()
+                            .nth(1)
+                            .map(|segment| segment.ident.to_string())
+                            .filter(|source| structs.contains_key(source) || *source == main_name)
+                            .unwrap_or_else(|| panic!("`#[boilermates(derive_field(...))]` needs a function defined on one of the structs, like `{}::function`", main_name));
+                        options.derive_field.push((strukt.clone(), field.clone(), source, function));
+                    }
+                    "field_order" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, order @ ..] = args.as_slice() else {
+                            panic!("`#[boilermates(field_order(...))]` must start with a struct name");
+                        };
+                        if !structs.contains_key(strukt) && *strukt != main_name {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        if options.field_order.insert(strukt.clone(), order.to_vec()).is_some() {
+                            panic!("`#[boilermates(field_order(...))]` is set more than once for `{}`", strukt);
+                        }
+                    }
+                    "split_in" => {
+                        let args = extract_nested_list(nv);
+                        let [strukt, composite, inner @ ..] = args.as_slice() else {
+                            panic!("`#[boilermates(split_in(...))]` must have the form `split_in(\"Struct\", \"field\", \"inner\", ...)`");
+                        };
+                        if !structs.contains_key(strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        if inner.is_empty() {
+                            panic!("`#[boilermates(split_in(...))]` must have at least one inner field");
+                        }
+                        options.split_in.push((strukt.clone(), composite.clone(), inner.to_vec()));
+                    }
+                    "deserialize_any_of" => {
+                        if !cfg!(feature = "serde") {
+                            panic!("`#[boilermates(deserialize_any_of(...))]` requires the `serde` feature");
+                        }
+                        let variants = extract_nested_list(nv);
+                        if variants.len() < 2 {
+                            panic!("`#[boilermates(deserialize_any_of(...))]` must have at least two arguments");
+                        }
+                        variants.iter().for_each(|v| {
+                            if *v != main_name && !structs.contains_key(v) {
+                                panic!("Struct `{}` not declared", v);
+                            }
+                        });
+                        options.deserialize_any_of.push(variants);
+                    }
+                    "from_tuple_for" => {
+                        let from_tuple_for = extract_nested_list(nv);
+                        if from_tuple_for.is_empty() {
+                            panic!("`#[boilermates(from_tuple_for(...))]` must have at least one argument");
+                        }
+                        from_tuple_for.iter().for_each(|strukt| {
+                            if *strukt != main_name && !structs.contains_key(strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                        });
+                        options.from_tuple_for.extend(from_tuple_for);
+                    }
+                    "validate_into_with_validator" => {
+                        if !cfg!(feature = "validator") {
+                            panic!("`#[boilermates(validate_into_with_validator(...))]` requires the `validator` feature");
+                        }
+                        let validate_into_with_validator = extract_nested_list(nv);
+                        if validate_into_with_validator.is_empty() {
+                            panic!("`#[boilermates(validate_into_with_validator(...))]` must have at least one argument");
+                        }
+                        validate_into_with_validator.iter().for_each(|strukt| {
+                            if *strukt != main_name && !structs.contains_key(strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                            if options.validated_into.contains_key(strukt) || options.validate_collect_into.contains(strukt) {
+                                panic!("`{}` can't be in both `validate_into_with_validator` and another validated conversion", strukt);
+                            }
+                            // Conversions that take arguments are validated like `validated_into` does
+                            options.validated_into.insert(
+                                strukt.clone(),
+                                (parse_quote!(::validator::Validate::validate), parse_quote!(::validator::ValidationErrors)),
+                            );
+                        });
+                        options.validate_into_with_validator.extend(validate_into_with_validator);
+                    }
+                    "stable_abi_for" => {
+                        let form = "`#[boilermates(stable_abi_for(...))]` must have the form `stable_abi_for(\"Struct\", size = n, offsets(field = n, ...))`";
+                        let parse_usize = |lit: &Lit| match lit {
+                            Lit::Int(int) => int.base10_parse::<usize>().unwrap_or_else(|e| panic!("Invalid layout: {}", e)),
+                            _ => panic!("{}", form),
+                        };
+                        let (
+                            3,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Meta(syn::Meta::NameValue(size))),
+                            Some(NestedMeta::Meta(syn::Meta::List(offsets))),
+                        ) = (nv.nested.len(), nv.nested.iter().next(), nv.nested.iter().nth(1), nv.nested.iter().nth(2)) else {
+                            panic!("{}", form);
+                        };
+                        if !size.path.is_ident("size") || !offsets.path.is_ident("offsets") {
+                            panic!("{}", form);
+                        }
+                        let strukt = strukt.value();
+                        if strukt != main_name && !structs.contains_key(&strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        let offsets = offsets.nested.iter().map(|offset| match offset {
+                            NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, lit, .. })) => {
+                                let field = path.get_ident().unwrap_or_else(|| panic!("{}", form));
+                                (field.to_string(), parse_usize(lit))
+                            }
+                            _ => panic!("{}", form),
+                        }).collect();
+                        options.stable_abi_for.push(StableAbi { strukt, offsets, size: parse_usize(&size.lit) });
+                    }
+                    "diff_patch" => {
+                        let [strukt, patch] = extract_nested_list(nv).try_into().unwrap_or_else(|_| {
+                            panic!("`#[boilermates(diff_patch(...))]` must have a struct name and a patch struct name")
+                        });
+                        if strukt != main_name && !structs.contains_key(&strukt) {
+                            panic!("Struct `{}` not declared", strukt);
+                        }
+                        if patch == main_name || structs.contains_key(&patch) {
+                            panic!("`#[boilermates(diff_patch(...))]` patch struct `{}` is already declared", patch);
+                        }
+                        options.diff_patch.push((strukt, patch));
+                    }
+                    "into_mut" => {
+                        let into_mut = extract_nested_list(nv);
+                        if into_mut.is_empty() {
+                            panic!("`#[boilermates(into_mut(...))]` must have at least one argument");
+                        }
+                        into_mut.iter().for_each(|strukt| {
+                            if *strukt != main_name && !structs.contains_key(strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                        });
+                        options.into_mut.extend(into_mut);
+                    }
+                    "ref_variant" => {
+                        let ref_variant = extract_nested_list(nv);
+                        if ref_variant.is_empty() {
+                            panic!("`#[boilermates(ref_variant(...))]` must have at least one argument");
+                        }
+                        ref_variant.iter().for_each(|strukt| {
+                            if *strukt != main_name && !structs.contains_key(strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                        });
+                        options.ref_variant.extend(ref_variant);
+                    }
+                    "from_bytes_for" => {
+                        if !cfg!(feature = "bincode") {
+                            panic!("`#[boilermates(from_bytes_for(...))]` requires the `bincode` feature");
+                        }
+                        let from_bytes_for = extract_nested_list(nv);
+                        if from_bytes_for.is_empty() {
+                            panic!("`#[boilermates(from_bytes_for(...))]` must have at least one argument");
+                        }
+                        from_bytes_for.iter().for_each(|strukt| {
+                            if *strukt != main_name && !structs.contains_key(strukt) {
+                                panic!("Struct `{}` not declared", strukt);
+                            }
+                        });
+                        options.from_bytes_for.extend(from_bytes_for);
+                    }
+                    _ => unknown_directive(ident, STRUCT_DIRECTIVES),
+                }
+            }
+
+            Some(syn::NestedMeta::Meta(syn::Meta::Path(path))) => {
+                let Some(ident) = path.get_ident() else { return true };
+                match ident.to_string().as_str() {
+                    "mockable_conversions" => options.mockable_conversions = true,
+                    "field_enum" => options.field_enum = true,
+                    "inline_conversions" => options.inline_conversions = true,
+                    "doc_matrix" => options.doc_matrix = true,
+                    "diff" => options.diff = true,
+                    "deref_getters" => options.deref_getters = true,
+                    "eq_source_ignoring_missing" => options.eq_source_ignoring_missing = true,
+                    "builder" => options.builder = true,
+                    "trace_conversions" => options.trace_conversions = true,
+                    "provenance" => options.provenance = true,
+                    "seal_conversions" => options.seal_conversions = true,
+                    "collect_errors" => options.collect_errors = true,
+                    "free_fn_conversions" => options.free_fn_conversions = true,
+                    "schema" => {
+                        if !cfg!(feature = "serde") {
+                            panic!("`#[boilermates(schema)]` requires the `serde` feature");
+                        }
+                        options.schema = true;
+                    }
+                    "result_into" => options.result_into = true,
+                    "context_struct" => options.context_struct = true,
+                    "auto_wrap" => options.auto_wrap = true,
+                    "constructor" => options.constructor = true,
+                    "map_values_convert" => options.map_values_convert = true,
+                    "iter_convert" => options.iter_convert = true,
+                    "converter_traits" => options.converter_traits = true,
+                    "dry_run" => options.dry_run = true,
+                    "track_caller" => options.track_caller = true,
+                    "serde_default_for_boilermates_defaults" => {
+                        if !cfg!(feature = "serde") {
+                            panic!("`#[boilermates(serde_default_for_boilermates_defaults)]` requires the `serde` feature");
+                        }
+                        options.serde_default_for_boilermates_defaults = true;
+                    }
+                    _ => unknown_directive(ident, STRUCT_DIRECTIVES),
+                }
+            }
+
+            _ => return true,
+        }
+        false
+    });
+
+    options.extends.keys().for_each(|variant| {
+        let mut current = variant.as_str();
+        for _ in 0..=options.extends.len() {
+            current = match options.extends.get(current) {
+                Some(extends) => &extends.base,
+                None => return,
+            };
+        }
+        panic!("`#[boilermates(extends(...))]` has a cycle through `{}`", variant);
+    });
+
+    options.extends.values().flat_map(|e| e.add.iter()).for_each(|added| {
+        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == added)) {
+            panic!("`#[boilermates(extends(...))]` adds unknown field `{}`", added);
+        }
+    });
+
+    options.id_field.iter().for_each(|id_field| {
+        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == id_field)) {
+            panic!("`#[boilermates(id_field(...))]` has unknown field `{}`", id_field);
+        }
+    });
+
+    options.zip_into.iter().flat_map(|(_, _, derived)| derived.sources()).for_each(|source| {
+        if !fields.named.iter().any(|f| f.ident.as_ref() == Some(source)) {
+            panic!("`#[boilermates(zip_into(...))]` has unknown field `{}`", source);
+        }
+    });
+
+    options.unwrap_result_in.values().flatten().for_each(|unwrapped| {
+        let field = fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == unwrapped))
+            .unwrap_or_else(|| panic!("`#[boilermates(unwrap_result_in(...))]` has unknown field `{}`", unwrapped));
+        if result_types(&field.ty).is_none() {
+            panic!("`#[boilermates(unwrap_result_in(...))]` needs field `{}` to be a `Result`", unwrapped);
+        }
+    });
+
+    options.split_in.iter().for_each(|(_, composite, _)| {
+        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == composite)) {
+            panic!("`#[boilermates(split_in(...))]` has unknown field `{}`", composite);
+        }
+    });
+
+    options.revalidate_into.keys().for_each(|strukt| {
+        if options.validated_into.contains_key(strukt) || options.validate_collect_into.contains(strukt) {
+            panic!("`{}` can't be in both `revalidate_into` and another validated conversion", strukt);
+        }
+    });
+
+    options.cast_in.values().flatten().for_each(|(cast, ty)| {
+        let field = fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == cast))
+            .unwrap_or_else(|| panic!("`#[boilermates(cast_in(...))]` has unknown field `{}`", cast));
+        if !options.auto_wrap && (wraps(ty, &field.ty) || wraps(&field.ty, ty)) {
+            panic!("`#[boilermates(cast_in(...))]` needs `#[boilermates(auto_wrap)]` to add or remove an `Option` around `{}`", cast);
+        }
+    });
+
+    options.clamp_in.iter().for_each(|(strukt, clamped)| {
+        clamped.iter().for_each(|clamped| {
+            let field = fields
+                .named
+                .iter()
+                .find(|f| f.ident.as_ref().is_some_and(|i| i == clamped))
+                .unwrap_or_else(|| panic!("`#[boilermates(clamp_in(...))]` has unknown field `{}`", clamped));
+            let Some((_, ty)) = options.cast_in.get(strukt).and_then(|cast| cast.iter().find(|(c, _)| c == clamped)) else {
+                panic!("`#[boilermates(clamp_in(...))]` needs field `{}` to be cast with `cast_in` in `{}`", clamped, strukt);
+            };
+            [&field.ty, ty].into_iter().for_each(|ty| {
+                if !is_clampable(ty) {
+                    panic!("`#[boilermates(clamp_in(...))]` needs the types of field `{}` to be integers other than `u128`, got `{}`", clamped, quote!(#ty));
+                }
+            });
+        });
+    });
+
+    options.cow_collection_in.values().flatten().for_each(|cow| {
+        let field = fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == cow))
+            .unwrap_or_else(|| panic!("`#[boilermates(cow_collection_in(...))]` has unknown field `{}`", cow));
+        if vec_inner(&field.ty).is_none() {
+            panic!("`#[boilermates(cow_collection_in(...))]` needs field `{}` to be a `Vec`", cow);
+        }
+    });
+
+    options.monomorphize.iter().for_each(|(name, params)| {
+        params.iter().for_each(|(param, _)| {
+            if !generic_params.contains(param) {
+                panic!("`#[boilermates(monomorphize(...))]` has unknown type parameter `{}` for `{}`", param, main_name);
+            }
+        });
+        generic_params.iter().for_each(|param| {
+            if !params.iter().any(|(p, _)| p == param) {
+                panic!("`#[boilermates(monomorphize(...))]` must give `{}` a type for `{}`", name, param);
+            }
+        });
+    });
+
+    options.unwrap_newtype_in.values().flatten().for_each(|(newtype, _)| {
+        let field = fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == newtype))
+            .unwrap_or_else(|| panic!("`#[boilermates(unwrap_newtype_in(...))]` has unknown field `{}`", newtype));
+        if !matches!(&field.ty, syn::Type::Path(path) if path.qself.is_none()) {
+            panic!("`#[boilermates(unwrap_newtype_in(...))]` needs `{}` to have a tuple struct type", newtype);
+        }
+    });
+
+    options.nested_convert.values().flatten().for_each(|(nested, _)| {
+        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == nested)) {
+            panic!("`#[boilermates(nested_convert(...))]` has unknown field `{}`", nested);
+        }
+    });
+
+    options.truncate_array_in.values().flatten().for_each(|(truncated, len)| {
+        let field = fields
+            .named
+            .iter()
+            .find(|f| f.ident.as_ref().is_some_and(|i| i == truncated))
+            .unwrap_or_else(|| panic!("`#[boilermates(truncate_array_in(...))]` has unknown field `{}`", truncated));
+        match array_len(&field.ty) {
+            Some(field_len) if *len <= field_len => {}
+            Some(field_len) => panic!("Can't truncate `{}` of length {} to length {}", truncated, field_len, len),
+            None => panic!("`#[boilermates(truncate_array_in(...))]` needs field `{}` to be an array with a literal length", truncated),
+        }
+    });
+
+    options.drop_into_flatten.iter().for_each(|flatten| {
+        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == flatten)) {
+            panic!("`#[boilermates(drop_into_flatten(...))]` has unknown field `{}`", flatten);
+        }
+    });
+
+    options.warn_on_drop.iter().for_each(|dropped| {
+        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == dropped)) {
+            panic!("`#[boilermates(warn_on_drop(...))]` has unknown field `{}`", dropped);
+        }
+    });
+
+    options.take_in.iter().for_each(|taken| {
+        if !fields.named.iter().any(|f| f.ident.as_ref().is_some_and(|i| i == taken)) {
+            panic!("`#[boilermates(take_in(...))]` has unknown field `{}`", taken);
+        }
+    });
+
+    fn extract_nested_list(meta_list: &syn::MetaList) -> Vec<String> {
+        meta_list
+            .nested
+            .iter()
+            .map(|n| match n {
+                NestedMeta::Lit(Lit::Str(lit)) => lit.value().trim_matches('"').to_owned(),
+                _ => panic!("Expected a string literal"),
+            })
+            .collect()
+    }
+
+    structs.insert(
+        main.ident.to_string(),
+        Struct {
+            attrs: main.attrs.clone(),
+            fields: vec![],
+            by_name: HashMap::new(),
+        },
+    );
+
+    let mut traits = quote! {};
+    // Lints that trivial accessors can trip, which the user can't fix in generated code
+    let accessor_allows = quote! {
+        #[allow(
+            clippy::must_use_candidate,
+            clippy::missing_const_for_fn,
+            clippy::trivially_copy_pass_by_ref,
+            clippy::missing_docs_in_private_items
+        )]
+    };
+    // The error returned by fallible getters and builders of fields that aren't set
+    let field_missing = Ident::new(&format!("{}FieldMissing", main_name), Span::call_site());
+    let mut has_fallible_getters = false;
+    // The error collected for every field that fails validation
+    let field_error = Ident::new(&format!("{}FieldError", main_name), Span::call_site());
+    // The inner fields declared for each nested field, which serde writes as keys of the outer struct when the
+    // field is `#[serde(flatten)]`
+    let mut flattened_keys = HashMap::<Ident, Vec<Ident>>::new();
+
+    fields.named.iter_mut().for_each(|field| {
+        let mut add_to = structs.keys().cloned().collect::<Vec<_>>();
+        let mut default = false;
+        let mut fallible_getter = false;
+        let mut cow_getter = false;
+        let mut aliases = vec![];
+        let mut validate_with = None;
+        let mut flatten = HashMap::<String, Vec<FieldConfig>>::new();
+        let (field_ident, field_vis) = (field.ident.clone(), field.vis.clone());
+        field.attrs.retain(|attr| {
+            let Ok(meta) = attr.parse_meta() else { return true };
+            let syn::Meta::List(list) = meta  else { return true };
+            let Some(name) = list.path.get_ident() else { return true };
+            if name != "boilermates" { return true }
+            match list.nested.first() {
+                Some(syn::NestedMeta::Meta(syn::Meta::List(nv))) => {
+                    let Some(ident) = nv.path.get_ident() else { panic!("#[boilermates] parsing error") };
+                    let ident = ident.to_string();
+                    if ident == "only_in" {
+                        let nested = extract_nested_list(nv);
+                        if nested.is_empty() {
+                            panic!(
+                                "`#[boilermates(only_in(...))]` must have at least one argument"
+                            );
+                        }
+                        // All variants are declared by the attribute arguments before any field is read,
+                        // so a field can name any of them regardless of where they appear
+                        nested.iter().for_each(|n| {
+                            if !structs.contains_key(n) {
+                                panic!(
+                                    "`#[boilermates(only_in(...))]` has undeclared struct name `{}`, declare it with `#[boilermates(\"{}\")]`",
+                                    n, n
+                                );
+                            }
+                        });
+                        add_to.retain(|s| nested.iter().any(|n| s == n.as_str()));
+                    } else if ident == "not_in" {
+                        let nested = extract_nested_list(nv);
+                        if nested.is_empty() {
+                            panic!(
+                                "`#[boilermates(not_in(...))]` must have at least one argument"
+                            );
+                        }
+                        nested.iter().for_each(|n| {
+                            if !structs.contains_key(n) {
+                                panic!(
+                                    "`#[boilermates(not_in(...))]` has undeclared struct name `{}`, declare it with `#[boilermates(\"{}\")]`",
+                                    n, n
+                                );
+                            }
+                        });
+                        add_to.retain(|s| !nested.iter().any(|n| s == n.as_str()));
+                    } else if ident == "alias_getter" {
+                        let nested = extract_nested_list(nv);
+                        if nested.is_empty() {
+                            panic!("`#[boilermates(alias_getter(...))]` must have at least one argument");
+                        }
+                        aliases.extend(nested.iter().map(|alias| Ident::new(alias, Span::call_site())));
+                    } else if ident == "validate_with" {
+                        let [validate] = extract_nested_list(nv).try_into().unwrap_or_else(|_| {
+                            panic!("`#[boilermates(validate_with(...))]` must have one string literal argument")
+                        });
+                        validate_with = Some(
+                            syn::parse_str::<syn::Expr>(&validate)
+                                .unwrap_or_else(|e| panic!("Could not parse validation function: {}", e)),
+                        );
+                    } else if ident == "flatten_from" {
+                        let (
+                            2,
+                            Some(NestedMeta::Lit(Lit::Str(strukt))),
+                            Some(NestedMeta::Meta(syn::Meta::List(inner_fields))),
+                        ) = (nv.nested.len(), nv.nested.iter().next(), nv.nested.iter().nth(1)) else {
+                            panic!("`#[boilermates(flatten_from(...))]` must have the form `flatten_from(\"Struct\", inner_fields(field = \"Type\", ...))`")
+                        };
+                        if !inner_fields.path.is_ident("inner_fields") {
+                            panic!("`#[boilermates(flatten_from(...))]` must have the form `flatten_from(\"Struct\", inner_fields(field = \"Type\", ...))`")
+                        }
+                        let strukt = strukt.value().trim_matches('"').to_owned();
+                        if !add_to.contains(&strukt) {
+                            panic!("`#[boilermates(flatten_from(...))]` has undeclared struct name `{}`", strukt);
+                        }
+                        let inner_fields = inner_fields.nested.iter().map(|inner| {
+                            let NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, lit: Lit::Str(ty), .. })) = inner else {
+                                panic!("`inner_fields(...)` arguments must have the form `field = \"Type\"`")
+                            };
+                            let ty = ty
+                                .parse::<syn::Type>()
+                                .unwrap_or_else(|e| panic!("Could not parse type: {}", e));
+                            let mut inner_field = FieldConfig::new(
+                                Field {
+                                    attrs: vec![],
+                                    vis: field_vis.clone(),
+                                    ident: path.get_ident().cloned(),
+                                    colon_token: Some(Default::default()),
+                                    ty,
+                                },
+                                false,
+                            );
+                            inner_field.derived = field_ident.clone().map(|parent| Derived::Flatten { parent });
+                            inner_field
+                        }).collect::<Vec<_>>();
+                        if let Some(field_ident) = &field_ident {
+                            let keys = flattened_keys.entry(field_ident.clone()).or_default();
+                            inner_fields.iter().map(FieldConfig::name).for_each(|key| {
+                                if !keys.contains(&key) { keys.push(key) }
+                            });
+                        }
+                        flatten.insert(strukt, inner_fields);
+                    } else {
+                        unknown_directive(&ident, FIELD_DIRECTIVES);
+                    }
+                }
+
+                Some(syn::NestedMeta::Meta(syn::Meta::Path(path))) => {
+                    let Some(ident) = path.get_ident() else { panic!("#[boilermates] parsing error") };
+                    match ident.to_string().as_str() {
+                        "default" => default = true,
+                        "fallible_getter" => fallible_getter = true,
+                        "cow_getter" => cow_getter = true,
+                        "only_in_self" => add_to = vec![main.ident.to_string()],
+                        _ => unknown_directive(ident, FIELD_DIRECTIVES),
+                    }
+                }
+
+                _ => return true,
+            }
+            
+            false
+        });
+
+        if !options.extends.is_empty() {
+            let field_name = field.ident.as_ref().map(ToString::to_string).unwrap_or_default();
+            add_to = structs
+                .keys()
+                .filter(|s| options.extends_contains(s, &field_name, &add_to, &main_name))
+                .cloned()
+                .collect();
+        }
+        add_to.retain(|s| !flatten.contains_key(s));
+        options.flat_nested.iter().for_each(|flat_nested| {
+            if flat_nested.inner.iter().any(|inner| field.ident.as_ref() == Some(inner)) {
+                add_to.retain(|s| *s != flat_nested.nested);
+            }
+        });
+
+        has_fallible_getters |= fallible_getter;
+        let lazy_default = field
+            .ident
+            .as_ref()
+            .and_then(|ident| options.lazy_default.iter().find(|(lazy, _)| ident == lazy))
+            .map(|(_, init)| init.clone());
+        let mut field = FieldConfig::new(field.clone(), default || lazy_default.is_some());
+        field.validate_with = validate_with;
+        field.self_ref = type_mentions(&field.field.ty, &main_name) || type_mentions(&field.field.ty, "Self");
+        // A trait can't name the type parameters of the struct, so fields of generic type don't get one
+        let generic = generic_params.iter().any(|param| type_mentions(&field.field.ty, &param.to_string()));
+        let trait_name = field.trait_name();
+        let neg_trait_name = field.neg_trait_name();
+        let field_name = field.name();
+        let setter_fn = Ident::new(&format!("set_{}", field_name), Span::call_site());
+        // Inside the traits, references to the struct are made through `Self`, which works for all variants
+        let field_ty = &rename_type(&field.field.ty, &main_name, &Ident::new("Self", Span::call_site()));
+        // Getters of smart pointer fields can return the value they point to, relying on deref coercion
+        let getter_ty = &options.deref_getters.then(|| pointee(field_ty)).flatten().unwrap_or_else(|| field_ty.clone());
+        let supertraits = if field.self_ref { quote! { : Sized } } else { quote! {} };
+        let same_entity_fn = if options.id_field.as_ref().is_some_and(|id_field| field_name == id_field) {
+            quote! {
+                fn same_entity(&self, other: &impl #trait_name) -> bool {
+                    self.#field_name() == other.#field_name()
+                }
+            }
+        } else {
+            quote! {}
+        };
+        let alias_getters = aliases.iter().map(|alias| quote! {
+            fn #alias(&self) -> &#getter_ty {
+                self.#field_name()
+            }
+        });
+        // For callers that only sometimes need to own the value
+        let cow_getter_fn = cow_getter.then(|| {
+            let cow_getter_fn_name = Ident::new(&format!("{}_cow", field_name), Span::call_site());
+            quote! {
+                fn #cow_getter_fn_name(&self) -> ::std::borrow::Cow<'_, #getter_ty> {
+                    ::std::borrow::Cow::Borrowed(self.#field_name())
+                }
+            }
+        });
+        if !generic {
+            traits = quote! {
+                #traits
+                #accessor_allows
+                trait #trait_name #supertraits {
+                    fn #field_name(&self) -> &#getter_ty;
+                    #(#alias_getters)*
+                    #cow_getter_fn
+                    fn #setter_fn(&mut self, value: #field_ty);
+                    #same_entity_fn
+                }
+
+                #accessor_allows
+                trait #neg_trait_name {}
+            };
+        }
+
+        // Lazy fields hold their value in a cell, which conversions leave empty when they don't have the field.
+        // The getter fills it on first access
+        if lazy_default.is_some() {
+            let ty = &field.field.ty;
+            field.field.ty = parse_quote!(::std::cell::OnceCell<#ty>);
+        }
+
+        structs.iter_mut().for_each(|(struct_name, strukt)| {
+            let struct_ident = Ident::new(struct_name, Span::call_site());
+
+            if add_to.contains(struct_name) {
+                let mut field = FieldConfig {
+                    rename: options.rename_in(struct_name, &field_name),
+                    ..field.clone()
+                };
+                if field.self_ref {
+                    field.field.ty = rename_type(&field.field.ty, &main_name, &struct_ident);
+                }
+                let unwrapped = options
+                    .unwrap_result_in
+                    .get(struct_name)
+                    .is_some_and(|unwrapped| unwrapped.iter().any(|u| field_name == u));
+                let cast_ty = options
+                    .cast_in
+                    .get(struct_name)
+                    .and_then(|cast| cast.iter().find(|(c, _)| field_name == c))
+                    .map(|(_, ty)| ty.clone());
+                let nested_ty = options
+                    .nested_convert
+                    .get(struct_name)
+                    .and_then(|nested| nested.iter().find(|(n, _)| field_name == n))
+                    .map(|(_, ty)| ty.clone());
+                let newtype_inner = options
+                    .unwrap_newtype_in
+                    .get(struct_name)
+                    .and_then(|newtypes| newtypes.iter().find(|(n, _)| field_name == n))
+                    .map(|(_, ty)| ty.clone());
+                let json = options
+                    .json_value_in
+                    .get(struct_name)
+                    .is_some_and(|json| json.iter().any(|j| field_name == j));
+                let cow = options
+                    .cow_collection_in
+                    .get(struct_name)
+                    .is_some_and(|cows| cows.iter().any(|c| field_name == c));
+                let truncated = options
+                    .truncate_array_in
+                    .get(struct_name)
+                    .and_then(|truncated| truncated.iter().find(|(t, _)| field_name == t))
+                    .map(|(_, len)| *len);
+                let field_ident = field.ident();
+                // The field has a different type than in the trait, so it doesn't implement it
+                if unwrapped {
+                    let (ok, error) = result_types(&field.field.ty).unwrap_or_else(|| panic!("Can't get `Result` types. This should never happen."));
+                    field.field.ty = ok;
+                    field.unwrap_result = Some(error);
+                } else if let Some(ty) = cast_ty {
+                    field.field.ty = ty;
+                    field.cast = true;
+                    field.clamped = options
+                        .clamp_in
+                        .get(struct_name)
+                        .is_some_and(|clamped| clamped.iter().any(|c| field_name == c));
+                } else if let Some(ty) = nested_ty {
+                    field.field.ty = ty;
+                    field.nested = true;
+                } else if let Some(ty) = newtype_inner {
+                    field.field.ty = ty;
+                    field.newtype = true;
+                } else if json {
+                    field.field.ty = parse_quote!(::serde_json::Value);
+                    field.json = true;
+                } else if let (true, Some(elem)) = (cow, vec_inner(&field.field.ty)) {
+                    field.field.ty = parse_quote!(::std::borrow::Cow<'static, [#elem]>);
+                    field.cow = true;
+                } else if let (Some(len), syn::Type::Array(array)) = (truncated, &field.field.ty) {
+                    let elem = &array.elem;
+                    let length = Literal::usize_unsuffixed(len);
+                    field.field.ty = parse_quote!([#elem; #length]);
+                    field.truncated = Some(len);
+                } else if !generic {
+                    let (getter_body, value) = match &lazy_default {
+                        Some(init) => (
+                            quote! { self.#field_ident.get_or_init(|| #init) },
+                            quote! { ::std::cell::OnceCell::from(value) },
+                        ),
+                        None => (quote! { &self.#field_ident }, quote! { value }),
+                    };
+                    traits = quote! {
+                        #traits
+                        #accessor_allows
+                        impl #trait_name for #struct_ident {
+                            fn #field_name(&self) -> &#getter_ty {
+                                #getter_body
+                            }
+
+                            fn #setter_fn(&mut self, value: #field_ty) {
+                                self.#field_ident = #value;
+                            }
+                        }
+                    };
+                }
+                if let Some(params) = options.monomorphize.get(struct_name).filter(|_| generic) {
+                    field.field.ty = substitute_types(&field.field.ty, params);
+                    field.monomorphized = true;
+                }
+                strukt.fields.push(field);
+            } else if !generic {
+                traits = quote! {
+                    #traits
+                    impl #neg_trait_name for #struct_ident {}
+                };
+            }
+
+            if fallible_getter {
+                let field_ident = options.rename_in(struct_name, &field_name).unwrap_or_else(|| field_name.clone());
+                let getter_fn = options.method_name(&format!("try_{}", field_name));
+                let key = field_name.to_string();
+                // The field can have another type in this struct, like a cast or unwrapped one
+                let struct_field_ty = strukt
+                    .fields
+                    .iter()
+                    .find(|f| f.name() == field_name)
+                    .map_or_else(|| field_ty.clone(), |f| f.field.ty.clone());
+                let (value_ty, value) = match option_inner(&struct_field_ty) {
+                    Some(inner) => (inner, quote! { self.#field_ident.as_ref().ok_or(#field_missing(#key)) }),
+                    None => (struct_field_ty, quote! { Ok(&self.#field_ident) }),
+                };
+                let value = if add_to.contains(struct_name) { value } else { quote! { Err(#field_missing(#key)) } };
+                traits = quote! {
+                    #traits
+                    impl #struct_ident {
+                        pub fn #getter_fn(&self) -> Result<&#value_ty, #field_missing> {
+                            #value
+                        }
+                    }
+                };
+            }
+
+            flatten.get(struct_name).into_iter().flatten().for_each(|inner_field| {
+                strukt.fields.push(FieldConfig {
+                    rename: options.rename_in(struct_name, &inner_field.name()),
+                    ..inner_field.clone()
+                });
+            });
+        });
+    });
+
+    if has_fallible_getters || options.builder {
+        let vis = &main.vis;
+        traits = quote! {
+            #traits
+            #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+            #vis struct #field_missing(pub &'static str);
+
+            impl ::std::fmt::Display for #field_missing {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    write!(f, "field `{}` is missing", self.0)
+                }
+            }
+
+            impl ::std::error::Error for #field_missing {}
+        };
+    }
+
+    if !options.validate_collect_into.is_empty() || options.collect_errors {
+        let vis = &main.vis;
+        traits = quote! {
+            #traits
+            #[derive(Clone, Debug, PartialEq, Eq)]
+            #vis struct #field_error {
+                pub field: &'static str,
+                pub message: String,
+            }
+
+            impl ::std::fmt::Display for #field_error {
+                fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                    write!(f, "field `{}` is invalid: {}", self.field, self.message)
+                }
+            }
+
+            impl ::std::error::Error for #field_error {}
+        };
+    }
+
+    options.flat_nested.iter().for_each(|flat_nested| {
+        let nested_field = flat_nested.field.ident.clone().unwrap_or_else(|| panic!("Can't get field name. This should never happen."));
+        flat_nested.inner.iter().for_each(|inner| {
+            if !structs[&flat_nested.flat].fields.iter().any(|f| f.name() == *inner) {
+                panic!("`#[boilermates(flat_nested(...))]` has unknown field `{}` for `{}`", inner, flat_nested.flat);
+            }
+        });
+        structs.iter_mut().for_each(|(name, strukt)| {
+            if *name == flat_nested.nested {
+                let mut field = FieldConfig::new(flat_nested.field.clone(), false);
+                field.derived = Some(Derived::Nest {
+                    ty: match &field.field.ty {
+                        syn::Type::Path(path) => path.path.clone(),
+                        ty => panic!("`#[boilermates(flat_nested(...))]` needs a struct type, got `{}`", quote!(#ty)),
+                    },
+                    fields: flat_nested.inner.clone(),
+                });
+                strukt.fields.push(field);
+            } else {
+                strukt.fields.iter_mut().filter(|f| flat_nested.inner.contains(&f.name())).for_each(|f| {
+                    f.derived = Some(Derived::Flatten { parent: nested_field.clone() });
+                });
+            }
+        });
+    });
+
+    options.zip_into.iter().for_each(|(name, field_name, derived)| {
+        let field = structs
+            .get_mut(name)
+            .and_then(|strukt| strukt.fields.iter_mut().find(|f| f.name() == field_name))
+            .unwrap_or_else(|| panic!("`#[boilermates(zip_into(...))]` has unknown field `{}` for `{}`", field_name, name));
+        field.derived = Some(derived.clone());
+    });
+
+    options.split_in.iter().for_each(|(name, composite, inner)| {
+        let strukt = structs.get_mut(name).unwrap_or_else(|| panic!("Struct `{}` not declared", name));
+        inner.iter().for_each(|inner| {
+            let field = strukt
+                .fields
+                .iter_mut()
+                .find(|f| f.name() == inner)
+                .unwrap_or_else(|| panic!("`#[boilermates(split_in(...))]` has unknown field `{}` for `{}`", inner, name));
+            field.derived = Some(Derived::Flatten { parent: Ident::new(composite, Span::call_site()) });
+        });
+    });
+
+    structs.iter().filter(|(name, _)| **name != main_name && !options.monomorphize.contains_key(*name)).for_each(|(name, strukt)| {
+        strukt.fields.iter().for_each(|field| {
+            if generic_params.iter().any(|param| type_mentions(&field.field.ty, &param.to_string())) {
+                panic!(
+                    "Field `{}` of `{}` has a generic type, so `{}` must be given concrete types with `#[boilermates(monomorphize(\"{}\", ...))]`",
+                    field.ident(),
+                    name,
+                    name,
+                    name
+                );
+            }
+        });
+    });
+
+    structs.iter().for_each(|(name, strukt)| {
+        strukt.fields.iter().enumerate().for_each(|(i, field)| {
+            if strukt.fields[..i].iter().any(|f| f.ident() == field.ident()) {
+                panic!(
+                    "Field `{}` appears more than once in `{}`",
+                    field.ident(),
+                    name
+                );
+            }
+        });
+    });
+
+    options.discriminant_in.iter().for_each(|(name, field_name, value)| {
+        let strukt = structs.get_mut(name).unwrap_or_else(|| panic!("Struct `{}` not declared", name));
+        match strukt.fields.iter_mut().find(|f| f.ident() == field_name) {
+            Some(field) => field.constant = Some(value.clone()),
+            None => {
+                // A new field gets the type of its literal value
+                let ty: syn::Type = match value {
+                    syn::Expr::Lit(syn::ExprLit { lit: Lit::Str(_), .. }) => parse_quote!(&'static str),
+                    syn::Expr::Lit(syn::ExprLit { lit: Lit::Bool(_), .. }) => parse_quote!(bool),
+                    syn::Expr::Lit(syn::ExprLit { lit: Lit::Char(_), .. }) => parse_quote!(char),
+                    syn::Expr::Lit(syn::ExprLit { lit: Lit::Int(int), .. }) if !int.suffix().is_empty() => {
+                        syn::parse_str(int.suffix()).unwrap_or_else(|e| panic!("Could not parse type: {}", e))
+                    }
+                    _ => panic!(
+                        "`#[boilermates(discriminant_in(...))]` can only add field `{}` to `{}` for a string, `bool`, `char` or suffixed integer literal. Declare the field to use another value",
+                        field_name, name
+                    ),
+                };
+                let mut field = FieldConfig::new(
+                    Field {
+                        attrs: vec![],
+                        vis: main.vis.clone(),
+                        ident: Some(Ident::new(field_name, Span::call_site())),
+                        colon_token: Some(Default::default()),
+                        ty,
+                    },
+                    false,
+                );
+                field.constant = Some(value.clone());
+                strukt.fields.push(field);
+            }
+        }
+    });
+
+    options.derive_field.iter().for_each(|(name, field, _, _)| {
+        if !structs[name].fields.iter().any(|f| f.name() == field) {
+            panic!("`#[boilermates(derive_field(...))]` has unknown field `{}` for `{}`", field, name);
+        }
+    });
+
+    options.field_order.iter().for_each(|(name, order)| {
+        let strukt = structs.get_mut(name).unwrap_or_else(|| panic!("Struct `{}` not declared", name));
+        let mut expected = strukt.fields.iter().map(|f| f.ident().to_string()).collect::<Vec<_>>();
+        let mut actual = order.clone();
+        expected.sort();
+        actual.sort();
+        if expected != actual {
+            panic!(
+                "`#[boilermates(field_order(...))]` for `{}` must list each of its fields once: {}",
+                name,
+                expected.join(", ")
+            );
+        }
+        strukt.fields.sort_by_key(|field| order.iter().position(|f| field.ident() == f));
+    });
+
+    options.proto_for.iter().for_each(|(name, numbers)| {
+        let strukt = structs
+            .get_mut(name)
+            .unwrap_or_else(|| panic!("Struct `{}` not declared", name));
+        numbers.iter().for_each(|(number_field, _)| {
+            if !strukt.fields.iter().any(|f| f.ident() == number_field) {
+                panic!("`#[boilermates(proto_for(...))]` has unknown field `{}` for `{}`", number_field, name);
+            }
+        });
+        strukt.fields.iter_mut().for_each(|field| {
+            let Some((_, tag)) = numbers.iter().find(|(f, _)| field.ident() == f) else {
+                panic!("Field `{}` of `{}` has no field number", field.ident(), name);
+            };
+            let tag = tag.to_string();
+            let kind = prost_kind(&field.field.ty);
+            field.field.attrs.push(parse_quote!(#[prost(#kind, tag = #tag)]));
+        });
+        strukt.fields.sort_by_key(|field| numbers.iter().find(|(f, _)| field.ident() == f).map(|(_, tag)| *tag));
+    });
+
+    options.field_attr_in.iter().for_each(|(name, field_name, attr)| {
+        let field = structs
+            .get_mut(name)
+            .and_then(|strukt| strukt.fields.iter_mut().find(|f| f.name() == field_name))
+            .unwrap_or_else(|| panic!("`#[boilermates(field_attr_in(...))]` has unknown field `{}` of `{}`", field_name, name));
+        field.field.attrs.push(attr.clone());
+    });
+
+    // The `with` of the struct replaces the one the field has everywhere else
+    options.serde_with_in.iter().for_each(|(name, field_name, module)| {
+        let field = structs
+            .get_mut(name)
+            .and_then(|strukt| strukt.fields.iter_mut().find(|f| f.name() == field_name))
+            .unwrap_or_else(|| panic!("`#[boilermates(serde_with_in(...))]` has unknown field `{}` of `{}`", field_name, name));
+        field.field.attrs.retain(|attr| {
+            !(attr.path.is_ident("serde")
+                && matches!(attr.parse_meta(), Ok(syn::Meta::List(list)) if list.nested.iter().any(|n| matches!(
+                    n,
+                    NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, .. })) if path.is_ident("with")
+                ))))
+        });
+        field.field.attrs.push(parse_quote!(#[serde(with = #module)]));
+    });
+
+    options.into_string_for.iter().for_each(|(name, expr)| {
+        let strukt = &structs[name];
+        self_fields(expr).iter().for_each(|field| {
+            if !strukt.fields.iter().any(|f| f.ident() == *field) {
+                panic!("`#[boilermates(into_string_for(...))]` uses unknown field `{}` of `{}`", field, name);
+            }
+        });
+    });
+
+    if options.serde_default_for_boilermates_defaults {
+        structs.values_mut().filter(|strukt| derives(&strukt.attrs, "Deserialize")).for_each(|strukt| {
+            strukt.fields.iter_mut().filter(|f| f.default).for_each(|field| {
+                let has_serde_default = field.field.attrs.iter().any(|attr| {
+                    attr.path.is_ident("serde")
+                        && matches!(attr.parse_meta(), Ok(syn::Meta::List(list)) if list.nested.iter().any(|n| matches!(
+                            n,
+                            NestedMeta::Meta(syn::Meta::Path(path)) | NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue { path, .. }))
+                                if path.is_ident("default")
+                        )))
+                });
+                if !has_serde_default {
+                    field.field.attrs.push(parse_quote!(#[serde(default)]));
+                }
+            });
+        });
+    }
+
+    if options.doc_matrix {
+        let doc = doc_matrix(&structs, &main_name);
+        structs
+            .get_mut(&main_name)
+            .unwrap_or_else(|| panic!("Can't find main struct. This should never happen."))
+            .attrs
+            .push(parse_quote!(#[doc = #doc]));
+    }
+
+    // Deriving `Copy` on a struct with a field that can't be `Copy` fails with an error pointing at the
+    // derive, so point out the offending field as well
+    structs.iter().filter(|(_, strukt)| derives(&strukt.attrs, "Copy")).for_each(|(name, strukt)| {
+        strukt.fields.iter().filter(|f| is_known_non_copy(&f.field.ty)).for_each(|field| {
+            let ty = &field.field.ty;
+            let warning = warning(
+                &format!(
+                    "`{}` derives `Copy`, but its field `{}` has type `{}`, which isn't `Copy`",
+                    name,
+                    field.ident(),
+                    quote!(#ty).to_string().replace(' ', "")
+                ),
+                syn::spanned::Spanned::span(ty),
+            );
+            traits = quote! { #traits #warning };
+        });
+    });
+
+    // The source a variant was converted from, which is left out of comparisons and hashes, so that variants with the
+    // same data are equal wherever they were converted from
+    let source_ty = Ident::new(&format!("{}Source", main_name), Span::call_site());
+    if options.provenance {
+        let vis = &main.vis;
+        traits = quote! {
+            #traits
+            #[derive(Clone, Copy, Debug, Default)]
+            #vis struct #source_ty(pub Option<&'static str>);
+
+            impl PartialEq for #source_ty {
+                fn eq(&self, _: &Self) -> bool {
+                    true
+                }
+            }
+
+            impl Eq for #source_ty {}
+
+            impl PartialOrd for #source_ty {
+                fn partial_cmp(&self, other: &Self) -> Option<::std::cmp::Ordering> {
+                    Some(self.cmp(other))
+                }
+            }
+
+            impl Ord for #source_ty {
+                fn cmp(&self, _: &Self) -> ::std::cmp::Ordering {
+                    ::std::cmp::Ordering::Equal
+                }
+            }
+
+            impl ::std::hash::Hash for #source_ty {
+                fn hash<H: ::std::hash::Hasher>(&self, _: &mut H) {}
+            }
+        };
+        // The main struct is the user's own, so its literals don't have to spell the field
+        structs.iter_mut().filter(|(name, _)| **name != main_name).for_each(|(_, strukt)| {
+            let mut attrs: Vec<Attribute> = vec![parse_quote!(#[doc(hidden)])];
+            if derives(&strukt.attrs, "Serialize") || derives(&strukt.attrs, "Deserialize") {
+                attrs.push(parse_quote!(#[serde(skip)]));
+            }
+            let mut field = FieldConfig::new(
+                Field {
+                    attrs,
+                    vis: main.vis.clone(),
+                    ident: Some(Ident::new("__boilermates_source", Span::call_site())),
+                    colon_token: Some(Default::default()),
+                    ty: parse_quote!(#source_ty),
+                },
+                false,
+            );
+            // Instances that weren't converted, like the ones built by constructors and builders, have no source
+            field.constant = Some(parse_quote!(#source_ty(None)));
+            field.provenance = true;
+            strukt.fields.push(field);
+        });
+    }
+
+    // Attributes attached to every generated conversion method
+    let mut conversion_attrs = if options.inline_conversions { quote! { #[inline] } } else { quote! {} };
+    if options.track_caller {
+        // Panics in hooks or `Into` impls called by a conversion then point at the caller of the conversion
+        conversion_attrs = quote! { #conversion_attrs #[track_caller] };
+    }
+    // `From` impls are as public as the types, so sealed conversions are crate-private inherent methods instead
+    let conversion_vis = if options.seal_conversions { quote! { pub(crate) } } else { quote! { pub } };
+    if options.seal_conversions {
+        let conflicting = [
+            ("mockable_conversions", options.mockable_conversions),
+            ("converter_traits", options.converter_traits),
+            ("map_values_convert", options.map_values_convert),
+            ("iter_convert", options.iter_convert),
+            ("free_fn_conversions", options.free_fn_conversions),
+            ("const_conversions", !options.const_conversions.is_empty()),
+            ("deserialize_any_of", !options.deserialize_any_of.is_empty()),
+        ];
+        if let Some((directive, _)) = conflicting.iter().find(|(_, enabled)| *enabled) {
+            panic!("`#[boilermates({})]` relies on `From` impls, which `#[boilermates(seal_conversions)]` doesn't generate", directive);
+        }
+        let self_refs = structs.values().flat_map(|strukt| &strukt.fields).filter(|field| field.self_ref).collect::<Vec<_>>();
+        if self_refs.iter().enumerate().any(|(i, field)| self_refs[..i].contains(field)) {
+            panic!("Fields that reference their own struct are converted between structs with `From` impls, which `#[boilermates(seal_conversions)]` doesn't generate");
+        }
+    }
+    if !options.validate_into_with_validator.is_empty() && options.seal_conversions {
+        panic!("`#[boilermates(validate_into_with_validator(...))]` generates `TryFrom` impls, which `#[boilermates(seal_conversions)]` doesn't allow");
+    }
+    if options.collect_errors && options.seal_conversions {
+        panic!("`#[boilermates(collect_errors)]` and `#[boilermates(seal_conversions)]` both generate `try_into_*` methods");
+    }
+    let from_impl = |name: &Ident, other_name: &Ident, body: TokenStream2| {
+        if options.seal_conversions {
+            let into_fn_name = options.method_name(&pascal_to_snake(&format!("into{}", name)));
+            quote! {
+                impl #other_name {
+                    #conversion_attrs
+                    #conversion_vis fn #into_fn_name(self) -> #name {
+                        let other = self;
+                        #body
+                    }
+                }
+            }
+        } else {
+            quote! {
+                impl From<#other_name> for #name {
+                    #conversion_attrs
+                    fn from(other: #other_name) -> Self {
+                        #body
+                    }
+                }
+            }
+        }
+    };
+
+    structs.values_mut().for_each(Struct::index_fields);
+
+    // Generated items are collected and spliced once at the end, rather than re-quoting the whole output for each
+    let mut output = Vec::<TokenStream2>::new();
+    // The targets of the `From` conversions of each struct, for the converter traits
+    let mut converters = HashMap::<String, Vec<(Ident, TokenStream2)>>::new();
+    // The conversions generated from each source into each target, as they're described by the spec
+    let mut spec_conversions = HashMap::<(String, String), Vec<String>>::new();
+    structs.iter().for_each(|(name, strukt)| {
+        let out_struct = DeriveInput {
+            attrs: strukt.attrs.clone(),
+            data: Data::Struct(DataStruct {
+                fields: Fields::Named(FieldsNamed {
+                    named: strukt
+                        .fields
+                        .iter()
+                        .cloned()
+                        .map(Into::<Field>::into)
+                        .collect(),
+                    ..fields
+                }),
+                ..data_struct
+            }),
+            ident: Ident::new(name, Span::call_site()),
+            // Only the main struct is generic, its variants have their type parameters substituted
+            generics: if *name == main_name { main.generics.clone() } else { Default::default() },
+            ..main.clone()
+        };
+        output.push(quote! {
+            #out_struct
+        });
+
+        if options.provenance && *name != main_name {
+            let struct_ident = Ident::new(name, Span::call_site());
+            let converted_from_fn_name = options.method_name("converted_from");
+            output.push(quote! {
+                impl #struct_ident {
+                    pub fn #converted_from_fn_name(&self) -> Option<&'static str> {
+                        self.__boilermates_source.0
+                    }
+                }
+            });
+        }
+
+        if options.field_enum {
+            let struct_ident = Ident::new(name, Span::call_site());
+            let enum_ident = Ident::new(&format!("{}Field", name), Span::call_site());
+            let get_fn_name = options.method_name("get");
+            let vis = &main.vis;
+            let field_idents = strukt.fields.iter().filter(|f| !f.provenance).map(FieldConfig::ident).collect::<Vec<_>>();
+            let variants = field_idents
+                .iter()
+                .map(|ident| Ident::new(&snake_to_pascal(&ident.to_string()), Span::call_site()))
+                .collect::<Vec<_>>();
+            output.push(quote! {
+                #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+                #vis enum #enum_ident {
+                    #( #variants, )*
+                }
+
+                impl #enum_ident {
+                    pub const ALL: &'static [Self] = &[ #( Self::#variants, )* ];
+                }
+
+                impl #struct_ident {
+                    pub fn #get_fn_name(&self, field: #enum_ident) -> &dyn ::std::any::Any {
+                        match field {
+                            #( #enum_ident::#variants => &self.#field_idents, )*
+                        }
+                    }
+                }
+            });
+        }
+
+        if options.constructor {
+            let struct_ident = Ident::new(name, Span::call_site());
+            let new_fn_name = options.method_name("new");
+            let (args, setters) = strukt.fields.iter().fold((quote! {}, quote! {}), |(args, setters), field| {
+                let field_ident = field.ident();
+                let field_ty = &field.field.ty;
+                if let Some(constant) = &field.constant {
+                    (args, quote! { #setters #field_ident: #constant, })
+                } else if field.default {
+                    (args, quote! { #setters #field_ident: Default::default(), })
+                } else {
+                    (quote! { #args #field_ident: #field_ty, }, quote! { #setters #field_ident, })
+                }
+            });
+            output.push(quote! {
+                impl #struct_ident {
+                    #[allow(clippy::too_many_arguments)]
+                    pub fn #new_fn_name(#args) -> Self {
+                        Self { #setters }
+                    }
+                }
+            });
+        }
+
+        if options.builder {
+            let struct_ident = Ident::new(name, Span::call_site());
+            let builder_ident = Ident::new(&format!("{}Builder", name), Span::call_site());
+            let builder_fn_name = options.method_name("builder");
+            let vis = &main.vis;
+            // Fields with a constant value can't be set
+            let (constants, fields): (Vec<_>, Vec<_>) = strukt.fields.iter().partition(|f| f.constant.is_some());
+            let constant_idents = constants.iter().map(|f| f.ident());
+            let constant_values = constants.iter().map(|f| &f.constant);
+            let field_idents = fields.iter().map(|f| f.ident()).collect::<Vec<_>>();
+            let field_tys = fields.iter().map(|f| &f.field.ty).collect::<Vec<_>>();
+            let values = fields.iter().map(|field| {
+                let field_ident = field.ident();
+                let key = field_ident.to_string();
+                if field.default {
+                    quote! { self.#field_ident.unwrap_or_default() }
+                } else {
+                    quote! { self.#field_ident.ok_or(#field_missing(#key))? }
+                }
+            });
+            output.push(quote! {
+                #[derive(Default)]
+                #vis struct #builder_ident {
+                    #( #field_idents: Option<#field_tys>, )*
+                }
+
+                impl #builder_ident {
+                    /// Seeds the builder with the fields of an existing value, to change some of them and rebuild it
+                    pub fn from_existing(value: #struct_ident) -> Self {
+                        Self { #( #field_idents: Some(value.#field_idents), )* }
+                    }
+
+                    #(
+                        pub fn #field_idents(mut self, value: #field_tys) -> Self {
+                            self.#field_idents = Some(value);
+                            self
+                        }
+                    )*
+
+                    pub fn build(self) -> Result<#struct_ident, #field_missing> {
+                        Ok(#struct_ident {
+                            #( #field_idents: #values, )*
+                            #( #constant_idents: #constant_values, )*
+                        })
+                    }
+                }
+
+                impl #struct_ident {
+                    pub fn #builder_fn_name() -> #builder_ident {
+                        Default::default()
+                    }
+                }
+            });
+        }
+
+        if options.iter_convert {
+            let struct_ident = Ident::new(name, Span::call_site());
+            let convert_fn_name = options.method_name("convert_iter");
+            // Each item is converted as it's pulled, so nothing is collected in between
+            output.push(quote! {
+                impl #struct_ident {
+                    #conversion_attrs
+                    pub fn #convert_fn_name<V: Into<Self>>(iter: impl IntoIterator<Item = V>) -> impl Iterator<Item = Self> {
+                        iter.into_iter().map(Into::into)
+                    }
+                }
+            });
+        }
+
+        if options.map_values_convert {
+            let struct_ident = Ident::new(name, Span::call_site());
+            let convert_fn_name = options.method_name("convert_map_values");
+            let convert_entries_fn_name = options.method_name("convert_entries");
+            output.push(quote! {
+                impl #struct_ident {
+                    #conversion_attrs
+                    pub fn #convert_fn_name<K: ::std::hash::Hash + Eq, V: Into<Self>>(
+                        map: ::std::collections::HashMap<K, V>,
+                    ) -> ::std::collections::HashMap<K, Self> {
+                        map.into_iter().map(|(key, value)| (key, value.into())).collect()
+                    }
+
+                    // Collecting into the caller's choice of map keeps the order of ordered maps
+                    #conversion_attrs
+                    pub fn #convert_entries_fn_name<K, V: Into<Self>, M: FromIterator<(K, Self)>>(
+                        entries: impl IntoIterator<Item = (K, V)>,
+                    ) -> M {
+                        entries.into_iter().map(|(key, value)| (key, value.into())).collect()
+                    }
+                }
+            });
+        }
+
+        if options.diff {
+            let struct_ident = Ident::new(name, Span::call_site());
+            let diff_fn_name = options.method_name("diff");
+            let field_idents = strukt.fields.iter().filter(|f| !f.provenance).map(FieldConfig::ident).collect::<Vec<_>>();
+            let field_names = field_idents.iter().map(ToString::to_string);
+            output.push(quote! {
+                impl #struct_ident {
+                    pub fn #diff_fn_name(&self, other: &Self) -> Vec<&'static str> {
+                        let mut changed = vec![];
+                        #(
+                            if self.#field_idents != other.#field_idents {
+                                changed.push(#field_names);
+                            }
+                        )*
+                        changed
+                    }
+                }
+            });
+        }
+
+        structs.iter().for_each(|(other_name, other)| {
+
+            if name == other_name { return }
+            let name = Ident::new(name, Span::call_site());
+            let other_name = Ident::new(other_name, Span::call_site());
+            // A struct with a single field holding the other struct is an envelope, which wraps and unwraps it
+            let mut spec_conversion = |conversion: String| {
+                spec_conversions.entry((name.to_string(), other_name.to_string())).or_default().push(conversion);
+            };
+            let sealed_into = || format!("{}()", options.method_name(&pascal_to_snake(&format!("into{}", name))));
+            if let Some(field) = strukt.envelope_of(&other_name) {
+                let field_ident = field.ident();
+                let conversion = from_impl(&name, &other_name, quote! { #name { #field_ident: other } });
+                output.push(conversion);
+                spec_conversion(if options.seal_conversions { sealed_into() } else { "From".to_owned() });
+                return;
+            }
+            if let Some(field) = other.envelope_of(&name) {
+                let field_ident = field.ident();
+                let conversion = from_impl(&name, &other_name, quote! { other.#field_ident });
+                output.push(conversion);
+                spec_conversion(if options.seal_conversions { sealed_into() } else { "From".to_owned() });
+                return;
+            }
+            // Fields computed from the whole source by a function
+            let computed = options
+                .derive_field
+                .iter()
+                .filter(|(target, _, source, _)| name == target && other_name == source)
+                .filter_map(|(_, field, _, function)| {
+                    let field = strukt.fields.iter().find(|f| f.name() == field)?;
+                    (!other.fields.contains(field)).then(|| (field.clone(), function.clone()))
+                })
+                .collect::<Vec<_>>();
+            // Fields dropped by the conversion, which are kept in the target's flattened catch-all field
+            let dropped_into_flatten = options.drop_into_flatten.as_ref().and_then(|flatten| {
+                let target_flatten = strukt.fields.iter().find(|f| f.name() == flatten)?;
+                other.fields.iter().find(|f| f.name() == flatten)?;
+                let dropped = other
+                    .fields
+                    .iter()
+                    .filter(|f| f.name() != flatten && !strukt.fields.contains(f))
+                    .map(|f| (f.name().to_string(), f.ident()))
+                    .collect::<Vec<_>>();
+                // A dropped field kept under the same key as a field of a flattened struct would be written twice
+                let target_keys = strukt
+                    .fields
+                    .iter()
+                    .filter(|f| serde_flatten(&f.field.attrs))
+                    .flat_map(|f| flattened_keys.get(&f.name()).into_iter().flatten().map(move |key| (key, f.ident())));
+                target_keys.for_each(|(key, nested)| {
+                    if let Some((_, dropped)) = dropped.iter().find(|(name, _)| key == name) {
+                        panic!(
+                            "Converting `{}` into `{}` keeps `{}` in `{}`, which is also a field of the flattened `{}`",
+                            other_name, name, dropped, flatten, nested
+                        );
+                    }
+                });
+                Some((target_flatten.ident(), dropped))
+            });
+            // Matching fields take a pass over both structs, so they're found once for each direction
+            let same_fields = strukt.same_fields_as(other);
+            // The fields both structs share are moved out of the source by a single destructure, into prefixed
+            // locals that can't shadow the source, hooks, transforms or functions that compute fields
+            let moved_local = |field: &Ident| Ident::new(&format!("__boilermates_{}", field), Span::call_site());
+            // Reads a field of the source from its local if it's moved by the destructure
+            let read_shared = |source: TokenStream2, field: &Ident| {
+                if same_fields.iter().any(|(_, other_field)| other_field.ident() == *field) {
+                    let local = moved_local(field);
+                    quote! { #local }
+                } else {
+                    quote! { #source.#field }
+                }
+            };
+            // Dropped fields are serialized into these locals by the checks, before they're kept in the target's map
+            let dropped_local = |field: &Ident| Ident::new(&format!("__boilermates_dropped_{}", field), Span::call_site());
+            // Builds the target from its field setters and `source`, the value being converted, running
+            // any steps that need the constructed target. Setters read shared fields from the locals of the
+            // destructure if `destructure` is set, and from `source` otherwise. `checks` run right before the
+            // target is built, once the shared fields are moved into their locals, and must set the locals of
+            // the fields dropped into the flattened map.
+            let construct_checked = |setters: TokenStream2, source: TokenStream2, destructure: bool, checks: TokenStream2| {
+                // Computed fields borrow the whole source, so they're computed before any field is moved out of it
+                let setters = strukt.fields.iter().fold(setters, |setters, field| match &field.constant {
+                    Some(_) if field.provenance => {
+                        let field_ident = field.ident();
+                        let source_name = other_name.to_string();
+                        quote! { #setters #field_ident: #source_ty(Some(#source_name)), }
+                    }
+                    Some(constant) => {
+                        let field_ident = field.ident();
+                        quote! { #setters #field_ident: #constant, }
+                    }
+                    None => setters,
+                });
+                let (pre, setters) = computed.iter().fold((quote! {}, setters), |(pre, setters), (field, function)| {
+                    let field_ident = field.ident();
+                    let value = Ident::new(&format!("__boilermates_{}", field_ident), Span::call_site());
+                    (
+                        quote! { #pre let #value = #function(&#source); },
+                        quote! { #setters #field_ident: #value, },
+                    )
+                });
+                let pre = if options.trace_conversions && cfg!(feature = "tracing") {
+                    let (source_name, target_name) = (other_name.to_string(), name.to_string());
+                    quote! {
+                        let __boilermates_span = ::tracing::trace_span!("boilermates_conversion", source = #source_name, target = #target_name).entered();
+                        ::tracing::trace!(source = #source_name, target = #target_name, "converting");
+                        #pre
+                    }
+                } else {
+                    pre
+                };
+                let mut pre = match &options.count_conversions {
+                    Some(metric) if cfg!(feature = "metrics") => {
+                        let (source_name, target_name) = (other_name.to_string(), name.to_string());
+                        quote! {
+                            ::metrics::counter!(#metric, "source" => #source_name, "target" => #target_name).increment(1);
+                            #pre
+                        }
+                    }
+                    _ => pre,
+                };
+                let mut post = quote! {};
+                if let Some((flatten, dropped)) = &dropped_into_flatten {
+                    dropped.iter().for_each(|(key, field)| {
+                        let value = dropped_local(field);
+                        post = quote! { #post target.#flatten.insert(#key.to_owned(), #value); };
+                    });
+                }
+                if let Some(hook) = options.post_convert.get(&name.to_string()) {
+                    post = quote! { #post #hook(&mut target); };
+                }
+                // Computed fields are computed above, while nothing has been moved out of the source yet
+                if destructure && !same_fields.is_empty() {
+                    let patterns = same_fields.iter().map(|(_, other_field)| {
+                        let (field, local) = (other_field.ident(), moved_local(&other_field.ident()));
+                        quote! { #field: #local }
+                    });
+                    pre = quote! { #pre let #other_name { #( #patterns, )* .. } = #source; };
+                }
+                let pre = quote! { #pre #checks };
+                if post.is_empty() {
+                    quote! { #pre #name { #setters } }
+                } else {
+                    quote! {
+                        #pre
+                        let mut target = #name { #setters };
+                        #post
+                        target
+                    }
+                }
+            };
+            let construct = |setters: TokenStream2, source: TokenStream2, destructure: bool| {
+                let checks = dropped_into_flatten.iter().flat_map(|(_, dropped)| dropped).fold(quote! {}, |checks, (_, field)| {
+                    let value = dropped_local(field);
+                    quote! { #checks let #value = ::serde_json::to_value(&#source.#field)?; }
+                });
+                construct_checked(setters, source, destructure, checks)
+            };
+            // Unwrapping `Result` fields makes the conversion fallible, with the error type of the first one
+            let unwrap_error = same_fields
+                .iter()
+                .find_map(|(field, other_field)| match &field.unwrap_result {
+                    Some(error) if other_field.unwrap_result.is_none() => Some(error.clone()),
+                    // Converting from or into JSON values can fail in both directions
+                    _ if field.json != other_field.json => Some(parse_quote!(::serde_json::Error)),
+                    _ => None,
+                })
+                // So can serializing the fields dropped into the flattened map
+                .or_else(|| {
+                    dropped_into_flatten
+                        .as_ref()
+                        .filter(|(_, dropped)| !dropped.is_empty())
+                        .map(|_| parse_quote!(::serde_json::Error))
+                });
+            // Conversions into a struct validated with `validator` are `TryFrom` impls instead of `From` ones
+            let validator = options.validate_into_with_validator.contains(&name.to_string());
+            if validator && unwrap_error.is_some() {
+                panic!("`{}` can't be validated with `validator` when its conversion from `{}` can fail", name, other_name);
+            }
+            // The return type of a conversion and its body, given the body that builds the target
+            let returning = |body: TokenStream2| match &unwrap_error {
+                Some(error) => (quote! { Result<#name, #error> }, quote! { Ok({ #body }) }),
+                None => (quote! { #name }, body),
+            };
+            let lossy = options.drops_warned(strukt, other);
+            let lossy_suffix = if lossy { "_lossy" } else { "" };
+            let mut missing_fields = strukt.missing_fields_from(other);
+            missing_fields.retain(|field| !computed.iter().any(|(f, _)| f == field));
+            let missing_fields_without_defaults = missing_fields
+                .iter()
+                .filter(|f| !f.default)
+                .collect::<Vec<_>>();
+
+            
+            // Struct literals evaluate in the order they're written, so defaults are evaluated in field declaration order
+            let default_field_setters = missing_fields.iter().filter(|f| f.default).fold(quote!{}, |acc, field| {
+                let field_name = field.ident();
+                quote! {
+                    #acc
+                    #field_name: Default::default(),
+                }
+            });
+            
+            if missing_fields_without_defaults.is_empty() {
+                let common_field_setters = same_fields.iter().fold(quote!{}, |acc, (field, other_field)| {
+                    let field_name = field.ident();
+                    let other_field_name = other_field.ident();
+                    let local = moved_local(&other_field_name);
+                    let value = field.convert_from(other_field, quote! { #local }, &other_name.to_string());
+                    let value = options.transform_value(&name.to_string(), field, value);
+                    quote! {
+                        #acc
+                        #field_name: #value,
+                    }
+                });
+                let common_field_setters = strukt.derived_fields_from(other, |name| read_shared(quote! { other }, name)).iter().fold(common_field_setters, |acc, (field, value)| {
+                    let field_name = field.ident();
+                    let value = options.transform_value(&name.to_string(), field, value.clone());
+                    quote! {
+                        #acc
+                        #field_name: #value,
+                    }
+                });
+
+                let body = construct(quote! { #common_field_setters #default_field_setters }, quote! { other }, true);
+                let fallible = if unwrap_error.is_some() { " -> Result" } else { "" };
+                output.push(match &unwrap_error {
+                    // Dropping a warned field takes an explicitly named method, which is generated below when
+                    // there are missing fields
+                    _ if lossy && !missing_fields.is_empty() => quote! {},
+                    _ if lossy => {
+                        let lossy_fn_name = options.method_name(&pascal_to_snake(&format!("into{}_lossy", name)));
+                        spec_conversion(format!("{}(){}", lossy_fn_name, fallible));
+                        let (ret, body) = returning(body);
+                        quote! {
+                            impl #other_name {
+                                #conversion_attrs
+                                #conversion_vis fn #lossy_fn_name(self) -> #ret {
+                                    let other = self;
+                                    #body
+                                }
+                            }
+                        }
+                    }
+                    Some(error) if options.seal_conversions => {
+                        let try_into_fn_name = options.method_name(&pascal_to_snake(&format!("try_into{}", name)));
+                        spec_conversion(format!("{}() -> Result", try_into_fn_name));
+                        quote! {
+                            impl #other_name {
+                                #conversion_attrs
+                                #conversion_vis fn #try_into_fn_name(self) -> Result<#name, #error> {
+                                    let other = self;
+                                    Ok({ #body })
+                                }
+                            }
+                        }
+                    }
+                    Some(error) => {
+                        spec_conversion("TryFrom".to_owned());
+                        quote! {
+                            impl TryFrom<#other_name> for #name {
+                                type Error = #error;
+
+                                #conversion_attrs
+                                fn try_from(other: #other_name) -> Result<Self, Self::Error> {
+                                    Ok({ #body })
+                                }
+                            }
+                        }
+                    }
+                    None if validator => {
+                        spec_conversion("TryFrom".to_owned());
+                        quote! {
+                            impl TryFrom<#other_name> for #name {
+                                type Error = ::validator::ValidationErrors;
+
+                                #conversion_attrs
+                                fn try_from(other: #other_name) -> Result<Self, Self::Error> {
+                                    let target = { #body };
+                                    ::validator::Validate::validate(&target)?;
+                                    Ok(target)
+                                }
+                            }
+                        }
+                    }
+                    None => {
+                        spec_conversion(if options.seal_conversions { sealed_into() } else { "From".to_owned() });
+                        from_impl(&name, &other_name, body)
+                    }
+                });
+
+                let has_results = same_fields
+                    .iter()
+                    .any(|(field, other_field)| field.unwrap_result.is_some() && other_field.unwrap_result.is_none());
+                if options.collect_errors && has_results && !lossy {
+                    let target = name.to_string();
+                    if options.validated_into.contains_key(&target) || options.validate_collect_into.contains(&target) {
+                        panic!("`{}` can't be validated with `#[boilermates(collect_errors)]`, since both generate `try_into_*` methods", target);
+                    }
+                    let try_into_fn_name = options.method_name(&pascal_to_snake(&format!("try_into{}", name)));
+                    // Every field whose conversion can fail is converted before the target is built, so all of
+                    // their errors are collected, and the target is built from the values that were `Ok`
+                    let mut checks = quote! {};
+                    let mut checked = vec![];
+                    let field_setters = same_fields.iter().fold(quote! {}, |acc, (field, other_field)| {
+                        let field_name = field.ident();
+                        let other_field_name = other_field.ident();
+                        let local = moved_local(&other_field_name);
+                        let value = match field.checked_convert_from(other_field, quote! { #local }, &other_name.to_string()) {
+                            (result, true) => {
+                                let value = Ident::new(&format!("__boilermates_checked_{}", field_name), Span::call_site());
+                                let key = other_field_name.to_string();
+                                checks = quote! {
+                                    #checks
+                                    let #value = match #result {
+                                        Ok(value) => Some(value),
+                                        Err(error) => {
+                                            errors.push(#field_error { field: #key, message: error.to_string() });
+                                            None
+                                        }
+                                    };
+                                };
+                                checked.push(value.clone());
+                                quote! { #value }
+                            }
+                            (value, false) => value,
+                        };
+                        let value = options.transform_value(&name.to_string(), field, value);
+                        quote! {
+                            #acc
+                            #field_name: #value,
+                        }
+                    });
+                    let field_setters = strukt.derived_fields_from(other, |name| read_shared(quote! { other }, name)).iter().fold(field_setters, |acc, (field, value)| {
+                        let field_name = field.ident();
+                        let value = options.transform_value(&name.to_string(), field, value.clone());
+                        quote! {
+                            #acc
+                            #field_name: #value,
+                        }
+                    });
+                    // Dropped fields that fail to serialize are collected with the fields that fail to convert
+                    let checks = dropped_into_flatten.iter().flat_map(|(_, dropped)| dropped).fold(checks, |checks, (key, field)| {
+                        let value = dropped_local(field);
+                        checked.push(value.clone());
+                        quote! {
+                            #checks
+                            let #value = match ::serde_json::to_value(&other.#field) {
+                                Ok(value) => Some(value),
+                                Err(error) => {
+                                    errors.push(#field_error { field: #key, message: error.to_string() });
+                                    None
+                                }
+                            };
+                        }
+                    });
+                    let checks = quote! {
+                        #checks
+                        let (#( Some(#checked), )*) = (#( #checked, )*) else {
+                            return Err(errors);
+                        };
+                    };
+                    let body = construct_checked(quote! { #field_setters #default_field_setters }, quote! { other }, true, checks);
+                    output.push(quote! {
+                        impl #other_name {
+                            #conversion_attrs
+                            #conversion_vis fn #try_into_fn_name(self) -> Result<#name, Vec<#field_error>> {
+                                let other = self;
+                                let mut errors = Vec::new();
+                                Ok({ #body })
+                            }
+                        }
+                    });
+                }
+
+                if options.free_fn_conversions && unwrap_error.is_none() && !validator && !lossy {
+                    let vis = &main.vis;
+                    let convert_fn_name = Ident::new(
+                        &pascal_to_snake(&format!("convert{}To{}", other_name, name)),
+                        Span::call_site()
+                    );
+                    output.push(quote! {
+                        #conversion_attrs
+                        #vis fn #convert_fn_name(other: #other_name) -> #name {
+                            other.into()
+                        }
+                    });
+                }
+
+                if options.mockable_conversions && unwrap_error.is_none() && !validator && !lossy {
+                    let mock_trait_name = Ident::new(&format!("{}To{}", other_name, name), Span::call_site());
+                    let to_fn_name = Ident::new(
+                        &pascal_to_snake(&format!("to{}", name)),
+                        Span::call_site()
+                    );
+                    let vis = &main.vis;
+                    // The source's impl is the real conversion, so a mock only has to implement the trait
+                    output.push(quote! {
+                        #vis trait #mock_trait_name {
+                            fn #to_fn_name(self) -> #name;
+                        }
+
+                        impl #mock_trait_name for #other_name {
+                            #conversion_attrs
+                            fn #to_fn_name(self) -> #name {
+                                self.into()
+                            }
+                        }
+                    });
+                }
+
+                // Converters borrow the source, so they clone only the fields the target takes from it
+                if options.converter_traits && unwrap_error.is_none() && !validator && !lossy {
+                    let cloning_field_setters = same_fields.iter().fold(quote!{}, |acc, (field, other_field)| {
+                        let field_name = field.ident();
+                        let other_field_name = other_field.ident();
+                        let value = field.convert_from(other_field, quote! { self.#other_field_name.clone() }, &other_name.to_string());
+                        let value = options.transform_value(&name.to_string(), field, value);
+                        quote! { #acc #field_name: #value, }
+                    });
+                    let cloning_field_setters = strukt.derived_fields_from(other, |name| quote! { self.#name.clone() }).iter().fold(cloning_field_setters, |acc, (field, value)| {
+                        let field_name = field.ident();
+                        let value = options.transform_value(&name.to_string(), field, value.clone());
+                        quote! { #acc #field_name: #value, }
+                    });
+                    let body = construct(quote! { #cloning_field_setters #default_field_setters }, quote! { (*self) }, false);
+                    converters.entry(other_name.to_string()).or_default().push((name.clone(), body));
+                }
+
+                // Fields of the source that the target doesn't have, named in the sink before converting
+                if options.into_with_sink.contains(&name.to_string()) && !validator {
+                    let into_with_sink_fn_name = options.method_name(&pascal_to_snake(&format!("into{}_with_sink{}", name, lossy_suffix)));
+                    let dropped = other
+                        .fields
+                        .iter()
+                        .filter(|f| strukt.field(&f.name()).is_none())
+                        .map(|f| f.name().to_string());
+                    let (ret, body) = returning(construct(quote! { #common_field_setters #default_field_setters }, quote! { other }, true));
+                    output.push(quote! {
+                        impl #other_name {
+                            #conversion_attrs
+                            #conversion_vis fn #into_with_sink_fn_name(self, sink: &mut Vec<String>) -> #ret {
+                                sink.extend([#(#dropped,)*].into_iter().map(str::to_owned));
+                                let other = self;
+                                #body
+                            }
+                        }
+                    });
+                }
+
+                if same_fields.iter().any(|(field, _)| options.take_in.contains(&field.name().to_string())) {
+                    let take_fn_name = options.method_name(&pascal_to_snake(&format!("take_into{}{}", name, lossy_suffix)));
+                    let taking_field_setters = same_fields.iter().fold(quote!{}, |acc, (field, other_field)| {
+                        let field_name = field.ident();
+                        let other_field_name = other_field.ident();
+                        let value = if options.take_in.contains(&field.name().to_string()) {
+                            quote! { ::std::mem::take(&mut self.#other_field_name) }
+                        } else {
+                            quote! { self.#other_field_name.clone() }
+                        };
+                        let value = field.convert_from(other_field, value, &other_name.to_string());
+                        let value = options.transform_value(&name.to_string(), field, value);
+                        quote! { #acc #field_name: #value, }
+                    });
+                    let taking_field_setters = strukt.derived_fields_from(other, |name| quote! { self.#name.clone() }).iter().fold(taking_field_setters, |acc, (field, value)| {
+                        let field_name = field.ident();
+                        let value = options.transform_value(&name.to_string(), field, value.clone());
+                        quote! { #acc #field_name: #value, }
+                    });
+                    let (ret, body) = returning(construct(quote! { #taking_field_setters #default_field_setters }, quote! { (*self) }, false));
+                    output.push(quote! {
+                        impl #other_name {
+                            #conversion_attrs
+                            #conversion_vis fn #take_fn_name(&mut self) -> #ret {
+                                #body
+                            }
+                        }
+                    });
+                }
+            }
+            // Converting fields that reference their own struct relies on `From`, so without it there's
+            // no way to fill in the missing fields of the nested values
+            let self_ref_without_from = !missing_fields_without_defaults.is_empty()
+                && same_fields.iter().any(|(field, _)| field.self_ref);
+            let common_field_setters = same_fields.iter().fold(quote!{}, |acc, (field, other_field)| {
+                let field_name = field.ident();
+                let other_field_name = other_field.ident();
+                let local = moved_local(&other_field_name);
+                let value = field.convert_from(other_field, quote! { #local }, &other_name.to_string());
+                let value = options.transform_value(&name.to_string(), field, value);
+                quote! {
+                    #acc
+                    #field_name: #value,
+                }
+            });
+            let common_field_setters = strukt.derived_fields_from(other, |name| read_shared(quote! { self }, name)).iter().fold(common_field_setters, |acc, (field, value)| {
+                let field_name = field.ident();
+                let value = options.transform_value(&name.to_string(), field, value.clone());
+                quote! {
+                    #acc
+                    #field_name: #value,
+                }
+            });
+
+            let into_defaults_args = missing_fields_without_defaults.iter().fold(quote!{}, |acc, field| {
+                let field_name = field.ident();
+                let field_ty = &field.field.ty;
+                quote! {
+                    #acc
+                    #field_name: #field_ty,
+                }
+            });
+
+            let into_defaults_missing_setters = missing_fields_without_defaults
+                .iter()
+                .fold(quote! {}, |acc, field| {
+                    let field_name = field.ident();
+                    quote! { #acc #field_name, }
+                });
+
+            if !missing_fields.is_empty() && !self_ref_without_from {
+                let into_args = missing_fields.iter().fold(quote!{}, |acc, field| {
+                    let field_name = field.ident();
+                    let field_ty = &field.field.ty;
+                    quote! {
+                        #acc
+                        #field_name: #field_ty,
+                    }
+                });
+
+                let into_missing_setters = missing_fields
+                    .iter()
+                    .fold(quote! {}, |acc, field| {
+                        let field_name = field.ident();
+                        quote! { #acc #field_name, }
+                    });
+
+                let into_defaults_fn_name = options.method_name(&pascal_to_snake(&format!("into{}_defaults{}", name, lossy_suffix)));
+                
+                let into_fn_name = options.method_name(&pascal_to_snake(&format!("into{}{}", name, lossy_suffix)));
+                let args = missing_fields
+                    .iter()
+                    .map(|f| {
+                        let ty = &f.field.ty;
+                        format!("{}: {}", f.ident(), quote!(#ty).to_string().replace(' ', ""))
+                    })
+                    .collect::<Vec<_>>();
+                let fallible = if unwrap_error.is_some() { " -> Result" } else { "" };
+                spec_conversion(format!("{}({}){}", into_fn_name, args.join(", "), fallible));
+
+                let (ret, into_body) = returning(construct(quote! { #common_field_setters #into_missing_setters }, quote! { self }, true));
+                let (_, into_defaults_body) = returning(construct(quote! {
+                    #common_field_setters
+                    #default_field_setters
+                    #into_defaults_missing_setters
+                }, quote! { self }, true));
+                output.push(quote! {
+                    impl #other_name {
+                        #conversion_attrs
+                        #[allow(clippy::too_many_arguments)]
+                        #conversion_vis fn #into_fn_name(self, #into_args) -> #ret {
+                            #into_body
+                        }
+
+                        #conversion_attrs
+                        #[allow(clippy::too_many_arguments)]
+                        #conversion_vis fn #into_defaults_fn_name(self, #into_defaults_args) -> #ret {
+                            #into_defaults_body
+                        }
+                    }
+                });
+
+                // The missing fields of a conversion, gathered in a struct, which a closure can fill in when it has defaults
+                let with_builder = options.into_with_builder.contains(&name.to_string());
+                let context_name = Ident::new(&format!("{}MissingFrom{}", name, other_name), Span::call_site());
+                if options.context_struct || with_builder {
+                    let context_fields = missing_fields.iter().map(|field| {
+                        let field_vis = &field.field.vis;
+                        let field_name = field.ident();
+                        let field_ty = &field.field.ty;
+                        quote! { #field_vis #field_name: #field_ty }
+                    });
+                    let vis = &main.vis;
+                    let derive_default = with_builder.then(|| quote! { #[derive(Default)] });
+                    output.push(quote! {
+                        #derive_default
+                        #vis struct #context_name {
+                            #(#context_fields,)*
+                        }
+                    });
+                }
+
+                if options.context_struct {
+                    let into_with_fn_name = options.method_name(&pascal_to_snake(&format!("into{}_with{}", name, lossy_suffix)));
+                    output.push(quote! {
+                        impl #other_name {
+                            #conversion_attrs
+                            #conversion_vis fn #into_with_fn_name(self, missing: #context_name) -> #ret {
+                                let #context_name { #into_missing_setters } = missing;
+                                #into_body
+                            }
+                        }
+                    });
+                }
+
+                if with_builder {
+                    let into_with_builder_fn_name = options.method_name(&pascal_to_snake(&format!("into{}_with_builder{}", name, lossy_suffix)));
+                    let field_idents = missing_fields.iter().map(FieldConfig::ident).collect::<Vec<_>>();
+                    let field_tys = missing_fields.iter().map(|field| &field.field.ty);
+                    output.push(quote! {
+                        impl #context_name {
+                            #(
+                                pub fn #field_idents(mut self, value: #field_tys) -> Self {
+                                    self.#field_idents = value;
+                                    self
+                                }
+                            )*
+                        }
+
+                        impl #other_name {
+                            #conversion_attrs
+                            #conversion_vis fn #into_with_builder_fn_name(self, build: impl FnOnce(#context_name) -> #context_name) -> #ret {
+                                let #context_name { #into_missing_setters } = build(Default::default());
+                                #into_body
+                            }
+                        }
+                    });
+                }
+            }
+
+            // Dropping a field runs its destructor, and computing, transforming or hooking into a conversion calls
+            // functions, which aren't `const` as far as the macro can tell
+            let const_evaluable = computed.is_empty()
+                && dropped_into_flatten.is_none()
+                && !options.post_convert.contains_key(&name.to_string())
+                && !(options.trace_conversions && cfg!(feature = "tracing"))
+                && !(options.count_conversions.is_some() && cfg!(feature = "metrics"))
+                && !other.fields.iter().any(|f| strukt.field(&f.name()).is_none() && is_known_non_copy(&f.field.ty))
+                && !same_fields.iter().any(|(field, _)| options.transforms(&name.to_string(), field));
+            // Without missing fields, there's no `into_*` method, so the `const` conversion can take its name
+            if options.const_conversions.contains(&name.to_string())
+                && const_evaluable
+                && missing_fields.is_empty()
+                && unwrap_error.is_none()
+                && !validator
+                && !lossy
+            {
+                let into_fn_name = options.method_name(&pascal_to_snake(&format!("into{}", name)));
+                spec_conversion(format!("const {}()", into_fn_name));
+                let body = construct(quote! { #common_field_setters }, quote! { self }, true);
+                output.push(quote! {
+                    impl #other_name {
+                        #conversion_attrs
+                        #conversion_vis const fn #into_fn_name(self) -> #name {
+                            #body
+                        }
+                    }
+                });
+            }
+
+            if let Some((validate, error)) = options.validated_into.get(&name.to_string()).filter(|_| !self_ref_without_from) {
+                let try_into_fn_name = options.method_name(&pascal_to_snake(&format!("try_into{}{}", name, lossy_suffix)));
+                let body = construct(quote! {
+                    #common_field_setters
+                    #default_field_setters
+                    #into_defaults_missing_setters
+                }, quote! { self }, true);
+                output.push(quote! {
+                    impl #other_name {
+                        #conversion_attrs
+                        #[allow(clippy::too_many_arguments)]
+                        #conversion_vis fn #try_into_fn_name(self, #into_defaults_args) -> Result<#name, #error> {
+                            let target = { #body };
+                            #validate(&target)?;
+                            Ok(target)
+                        }
+                    }
+                });
+            }
+
+            if let Some((revalidate, error)) = options.revalidate_into.get(&name.to_string()).filter(|_| !self_ref_without_from) {
+                let try_into_fn_name = options.method_name(&pascal_to_snake(&format!("try_into{}{}", name, lossy_suffix)));
+                let body = construct(quote! {
+                    #common_field_setters
+                    #default_field_setters
+                    #into_defaults_missing_setters
+                }, quote! { self }, true);
+                let field_idents = strukt.fields.iter().filter(|f| !f.provenance).map(FieldConfig::ident).collect::<Vec<_>>();
+                output.push(quote! {
+                    impl #other_name {
+                        #conversion_attrs
+                        #[allow(clippy::too_many_arguments)]
+                        #conversion_vis fn #try_into_fn_name(self, #into_defaults_args) -> Result<#name, #error> {
+                            let #name { #( #field_idents, )* .. } = { #body };
+                            #revalidate(#( #field_idents, )*)
+                        }
+                    }
+                });
+            }
+
+            if options.validate_collect_into.contains(&name.to_string()) && !self_ref_without_from {
+                let try_into_fn_name = options.method_name(&pascal_to_snake(&format!("try_into{}{}", name, lossy_suffix)));
+                let body = construct(quote! {
+                    #common_field_setters
+                    #default_field_setters
+                    #into_defaults_missing_setters
+                }, quote! { self }, true);
+                let validations = strukt.fields.iter().filter_map(|field| {
+                    let validate = field.validate_with.as_ref()?;
+                    let field_ident = field.ident();
+                    let field_str = field_ident.to_string();
+                    Some(quote! {
+                        if let Err(message) = #validate(&target.#field_ident) {
+                            errors.push(#field_error { field: #field_str, message: message.to_string() });
+                        }
+                    })
+                });
+                output.push(quote! {
+                    impl #other_name {
+                        #conversion_attrs
+                        #[allow(clippy::too_many_arguments)]
+                        #conversion_vis fn #try_into_fn_name(self, #into_defaults_args) -> Result<#name, Vec<#field_error>> {
+                            let target = { #body };
+                            #[allow(unused_mut)]
+                            let mut errors = Vec::new();
+                            #(#validations)*
+                            if errors.is_empty() { Ok(target) } else { Err(errors) }
+                        }
+                    }
+                });
+            }
+
+            if options.eq_source_ignoring_missing {
+                // Only structs whose fields are a subset of the other's are compared, ignoring the extra fields
+                let subset = same_fields.len() == strukt.fields.len() || same_fields.len() == other.fields.len();
+                let same_types = same_fields.iter().all(|(field, other_field)| {
+                    let (ty, other_ty) = (&field.field.ty, &other_field.field.ty);
+                    quote!(#ty).to_string() == quote!(#other_ty).to_string()
+                });
+                if subset && same_types {
+                    let (field_idents, other_field_idents): (Vec<_>, Vec<_>) = same_fields
+                        .iter()
+                        .map(|(field, other_field)| (field.ident(), other_field.ident()))
+                        .unzip();
+                    output.push(quote! {
+                        impl PartialEq<#other_name> for #name {
+                            fn eq(&self, other: &#other_name) -> bool {
+                                true #( && self.#field_idents == other.#other_field_idents )*
+                            }
+                        }
+                    });
+                }
+            }
+        })
+    });
+
+    let mut converters = converters.into_iter().collect::<Vec<_>>();
+    converters.sort_by(|(a, _), (b, _)| a.cmp(b));
+    converters.iter_mut().for_each(|(source, targets)| {
+        targets.sort_by_key(|(target, _)| target.to_string());
+        let (targets, bodies): (Vec<_>, Vec<_>) = targets.iter().cloned().unzip();
+        let source = Ident::new(source, Span::call_site());
+        let trait_name = Ident::new(&format!("{}Converter", source), Span::call_site());
+        let fn_names = targets
+            .iter()
+            .map(|target| Ident::new(&pascal_to_snake(&format!("convertTo{}", target)), Span::call_site()))
+            .collect::<Vec<_>>();
+        let vis = &main.vis;
+        output.push(quote! {
+            #vis trait #trait_name {
+                #( fn #fn_names(&self) -> #targets; )*
+            }
+
+            impl #trait_name for #source {
+                #(
+                    #conversion_attrs
+                    fn #fn_names(&self) -> #targets {
+                        #bodies
+                    }
+                )*
+            }
+        });
+    });
+
+    if options.dry_run {
+        let spec = spec(&structs, &spec_conversions);
+        let main_ident = &main.ident;
+        output.push(quote! {
+            impl #main_ident {
+                pub const BOILERMATES_SPEC: &'static str = #spec;
+            }
+        });
+    }
+
+    if options.schema {
+        let vis = &main.vis;
+        let type_name = |ty: &syn::Type| quote!(#ty).to_string().replace(' ', "");
+        let mut names = structs.keys().collect::<Vec<_>>();
+        names.sort();
+        names.iter().for_each(|name| {
+            let fields = structs[*name].fields.iter().filter(|f| !f.provenance).collect::<Vec<_>>();
+            let field_names = fields.iter().map(|f| f.ident().to_string());
+            let field_tys = fields.iter().map(|f| type_name(&f.field.ty));
+            let field_defaults = fields.iter().map(|f| f.default);
+            let schema_fn_name = Ident::new(&pascal_to_snake(&format!("{}Schema", name)), Span::call_site());
+            output.push(quote! {
+                #vis fn #schema_fn_name() -> ::serde_json::Value {
+                    ::serde_json::json!({
+                        #( #field_names: { "type": #field_tys, "default": #field_defaults }, )*
+                    })
+                }
+            });
+        });
+    }
+
+    // Tuples are only as long as the ones std implements traits for
+    options.from_tuple_for.iter().for_each(|name| {
+        let strukt = &structs[name];
+        let (constants, fields): (Vec<_>, Vec<_>) = strukt.fields.iter().partition(|f| f.constant.is_some());
+        if fields.len() > 12 {
+            panic!("`#[boilermates(from_tuple_for(...))]` needs `{}` to have at most 12 fields, it has {}", name, fields.len());
+        }
+        let name = Ident::new(name, Span::call_site());
+        let field_idents = fields.iter().map(|f| f.ident()).collect::<Vec<_>>();
+        let field_tys = fields.iter().map(|f| &f.field.ty).collect::<Vec<_>>();
+        let constant_idents = constants.iter().map(|f| f.ident());
+        let constant_values = constants.iter().map(|f| &f.constant);
+        output.push(quote! {
+            impl From<(#( #field_tys, )*)> for #name {
+                #conversion_attrs
+                fn from((#( #field_idents, )*): (#( #field_tys, )*)) -> Self {
+                    Self { #( #field_idents, )* #( #constant_idents: #constant_values, )* }
+                }
+            }
+
+            impl From<#name> for (#( #field_tys, )*) {
+                #conversion_attrs
+                fn from(other: #name) -> Self {
+                    (#( other.#field_idents, )*)
+                }
+            }
+        });
+    });
+
+    // Serde writes the fields of a flattened struct as keys of the outer one, so they can't share a name with its
+    // other fields. Only the inner fields declared with `flatten_from` are known.
+    structs.iter().for_each(|(name, strukt)| {
+        let mut keys = HashMap::<String, String>::new();
+        strukt.fields.iter().for_each(|field| {
+            let (field_keys, source) = if serde_flatten(&field.field.attrs) {
+                let field_keys = flattened_keys.get(&field.name()).cloned().unwrap_or_default();
+                (field_keys, format!("the flattened `{}`", field.ident()))
+            } else {
+                (vec![field.ident()], format!("`{}`", field.ident()))
+            };
+            field_keys.iter().for_each(|key| {
+                if let Some(other) = keys.insert(key.to_string(), source.clone()) {
+                    panic!("`{}` would serialize `{}` twice, from {} and {}", name, key, other, source);
+                }
+            });
+        });
+    });
+
+    if let Some(latest) = options.version.iter().map(|(_, version)| *version).max() {
+        let version_fn_name = options.method_name("version");
+        let latest_version_fn_name = options.method_name("latest_version");
+        options.version.iter().for_each(|(name, version)| {
+            let name = Ident::new(name, Span::call_site());
+            output.push(quote! {
+                impl #name {
+                    pub const fn #version_fn_name() -> u32 {
+                        #version
+                    }
+
+                    pub const fn #latest_version_fn_name() -> u32 {
+                        #latest
+                    }
+                }
+            });
+        });
+    }
+
+    // `#[repr(C)]` lays the fields out in the order `field_order` gives them, so the offsets are checked to
+    // increase in that order, and to be the ones pinned, which changes to the fields' types would move
+    options.stable_abi_for.iter().for_each(|StableAbi { strukt: name, offsets, size }| {
+        let Some(order) = options.field_order.get(name) else {
+            panic!("`#[boilermates(stable_abi_for(...))]` needs `#[boilermates(field_order(...))]` for `{}`", name);
+        };
+        if !repr_c(&structs[name].attrs) {
+            panic!("`#[boilermates(stable_abi_for(...))]` needs `{}` to be `#[repr(C)]`, for example with `#[boilermates(repr_for(\"{}\", \"C\"))]`", name, name);
+        }
+        let mut pinned = offsets.iter().map(|(field, _)| field).collect::<Vec<_>>();
+        let mut expected = order.iter().collect::<Vec<_>>();
+        pinned.sort();
+        expected.sort();
+        if pinned != expected {
+            panic!(
+                "`#[boilermates(stable_abi_for(...))]` for `{}` must give the offset of each of its fields once: {}",
+                name,
+                order.join(", ")
+            );
+        }
+        let order_message = format!("`{}` isn't laid out in the order given by `#[boilermates(field_order(...))]`", name);
+        let fields = order.iter().map(|f| Ident::new(f, Span::call_site())).collect::<Vec<_>>();
+        let (before, after) = (&fields[..fields.len().saturating_sub(1)], fields.iter().skip(1));
+        let offset_messages = offsets.iter().map(|(field, offset)| format!("`{}::{}` isn't at offset {}", name, field, offset));
+        let (offset_fields, offsets) = offsets.iter().map(|(field, offset)| (Ident::new(field, Span::call_site()), *offset)).unzip::<_, _, Vec<_>, Vec<_>>();
+        let size_message = format!("`{}` isn't {} bytes", name, size);
+        let name = Ident::new(name, Span::call_site());
+        output.push(quote! {
+            const _: () = {
+                #(
+                    assert!(
+                        ::core::mem::offset_of!(#name, #before) <= ::core::mem::offset_of!(#name, #after),
+                        #order_message
+                    );
+                )*
+                #(
+                    assert!(::core::mem::offset_of!(#name, #offset_fields) == #offsets, #offset_messages);
+                )*
+                assert!(::core::mem::size_of::<#name>() == #size, #size_message);
+            };
+        });
+    });
+
+    // Unchanged fields are `None`, so applying a patch only touches the fields that changed
+    options.diff_patch.iter().for_each(|(name, patch)| {
+        let fields = structs[name].fields.iter().filter(|f| !f.provenance).collect::<Vec<_>>();
+        let field_attrs = fields.iter().map(|f| f.field.attrs.iter().filter(|a| a.path.is_ident("doc")).collect::<Vec<_>>());
+        let field_vis = fields.iter().map(|f| &f.field.vis);
+        let field_idents = fields.iter().map(|f| f.ident()).collect::<Vec<_>>();
+        let field_tys = fields.iter().map(|f| &f.field.ty);
+        let diff_patch_fn_name = options.method_name("diff_patch");
+        let apply_patch_fn_name = options.method_name("apply_patch");
+        let name = Ident::new(name, Span::call_site());
+        let patch = Ident::new(patch, Span::call_site());
+        let vis = &main.vis;
+        output.push(quote! {
+            #[derive(Clone, Default, PartialEq)]
+            #vis struct #patch {
+                #( #(#field_attrs)* #field_vis #field_idents: Option<#field_tys>, )*
+            }
+
+            impl #name {
+                pub fn #diff_patch_fn_name(&self, other: &Self) -> #patch {
+                    #patch {
+                        #( #field_idents: (self.#field_idents != other.#field_idents).then(|| other.#field_idents.clone()), )*
+                    }
+                }
+
+                pub fn #apply_patch_fn_name(&mut self, patch: #patch) {
+                    #(
+                        if let Some(value) = patch.#field_idents {
+                            self.#field_idents = value;
+                        }
+                    )*
+                }
+            }
+        });
+    });
+
+    // Reborrowing is only sound between `#[repr(C)]` structs with the same fields in the same order,
+    // which gives them the same layout
+    options.into_mut.iter().for_each(|name| {
+        let layout = |strukt: &Struct| {
+            strukt.fields.iter().map(|f| (f.ident(), { let ty = &f.field.ty; quote!(#ty).to_string() })).collect::<Vec<_>>()
+        };
+        let target = &structs[name];
+        if !repr_c(&target.attrs) {
+            panic!("`#[boilermates(into_mut(...))]` needs `{}` to be `#[repr(C)]`", name);
+        }
+        let mut sources = structs
+            .iter()
+            .filter(|(other, strukt)| *other != name && repr_c(&strukt.attrs) && layout(strukt) == layout(target))
+            .map(|(other, _)| Ident::new(other, Span::call_site()))
+            .collect::<Vec<_>>();
+        if sources.is_empty() {
+            panic!(
+                "`#[boilermates(into_mut(...))]` needs another `#[repr(C)]` struct with the same fields as `{}`, in the same order",
+                name
+            );
+        }
+        sources.sort();
+        let as_mut_fn_name = options.method_name(&pascal_to_snake(&format!("as{}_mut", name)));
+        let name = Ident::new(name, Span::call_site());
+        output.push(quote! {
+            #(
+                impl #sources {
+                    #conversion_attrs
+                    pub fn #as_mut_fn_name(&mut self) -> &mut #name {
+                        // SAFETY: both structs are `#[repr(C)]` with the same field types in the same order
+                        unsafe { &mut *(self as *mut Self as *mut #name) }
+                    }
+                }
+            )*
+        });
+    });
+
+    // The view borrows every field of the struct, so `as_*` ties it to `&self` through `'_`
+    options.ref_variant.iter().for_each(|name| {
+        let strukt = &structs[name];
+        let view = Ident::new(&format!("{}Ref", name), Span::call_site());
+        let as_fn_name = options.method_name(&pascal_to_snake(&format!("as{}", view)));
+        let name = Ident::new(name, Span::call_site());
+        let field_attrs = strukt.fields.iter().map(|f| f.field.attrs.iter().filter(|a| a.path.is_ident("doc")).collect::<Vec<_>>());
+        let field_vis = strukt.fields.iter().map(|f| &f.field.vis);
+        let field_idents = strukt.fields.iter().map(|f| f.ident()).collect::<Vec<_>>();
+        let field_tys = strukt.fields.iter().map(|f| &f.field.ty);
+        let vis = &main.vis;
+        output.push(quote! {
+            #[derive(Clone, Copy)]
+            #vis struct #view<'a> {
+                #( #(#field_attrs)* #field_vis #field_idents: &'a #field_tys, )*
+            }
+
+            impl #name {
+                #conversion_attrs
+                pub fn #as_fn_name(&self) -> #view<'_> {
+                    #view { #( #field_idents: &self.#field_idents, )* }
+                }
+            }
+
+            impl<'a> From<&'a #name> for #view<'a> {
+                #conversion_attrs
+                fn from(other: &'a #name) -> Self {
+                    other.#as_fn_name()
+                }
+            }
+        });
+    });
+
+    options.from_bytes_for.iter().for_each(|name| {
+        let name = Ident::new(name, Span::call_site());
+        let to_bytes_fn_name = options.method_name("to_bytes");
+        output.push(quote! {
+            impl TryFrom<&[u8]> for #name {
+                type Error = ::bincode::Error;
+
+                fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+                    ::bincode::deserialize(bytes)
+                }
+            }
+
+            impl #name {
+                pub fn #to_bytes_fn_name(&self) -> Result<Vec<u8>, ::bincode::Error> {
+                    ::bincode::serialize(self)
+                }
+            }
+        });
+    });
+
+    options.deserialize_any_of.iter().for_each(|variants| {
+        // Newer shapes are tried first, so that an older shape can't swallow a newer payload
+        // by ignoring its extra fields
+        let variants = variants.iter().rev().map(|v| Ident::new(v, Span::call_site())).collect::<Vec<_>>();
+        let latest = &variants[0];
+        let older = &variants[1..];
+        let wrapper = Ident::new(&format!("{}AnyOf", latest), Span::call_site());
+        let vis = &main.vis;
+        output.push(quote! {
+            #vis struct #wrapper(pub #latest);
+
+            impl<'de> ::serde::Deserialize<'de> for #wrapper {
+                fn deserialize<D: ::serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                    #[derive(::serde::Deserialize)]
+                    #[serde(untagged)]
+                    enum Shape {
+                        #( #variants(#variants), )*
+                    }
+
+                    Ok(Self(match <Shape as ::serde::Deserialize>::deserialize(deserializer)? {
+                        Shape::#latest(latest) => latest,
+                        #( Shape::#older(older) => older.into(), )*
+                    }))
+                }
+            }
+
+            impl From<#wrapper> for #latest {
+                #conversion_attrs
+                fn from(wrapper: #wrapper) -> Self {
+                    wrapper.0
+                }
+            }
+        });
+    });
+
+    options.proto_into.iter().for_each(|proto| {
+        let main_ident = &main.ident;
+        let (to_proto, from_proto) = structs[&main_name].fields.iter().fold((quote! {}, quote! {}), |(to_proto, from_proto), field| {
+            let field_name = field.ident();
+            if let Some(constant) = &field.constant {
+                (to_proto, quote! { #from_proto #field_name: #constant, })
+            } else if is_option(&field.field.ty) {
+                (
+                    quote! { #to_proto #field_name: other.#field_name, },
+                    quote! { #from_proto #field_name: other.#field_name, },
+                )
+            } else {
+                (
+                    quote! { #to_proto #field_name: Some(other.#field_name), },
+                    quote! { #from_proto #field_name: other.#field_name.unwrap_or_default(), },
+                )
+            }
+        });
+        output.push(quote! {
+            impl From<#main_ident> for #proto {
+                #conversion_attrs
+                fn from(other: #main_ident) -> Self {
+                    #proto { #to_proto }
+                }
+            }
+
+            impl From<#proto> for #main_ident {
+                #conversion_attrs
+                fn from(other: #proto) -> Self {
+                    Self { #from_proto }
+                }
+            }
+        });
+    });
+
+    // The external type's definition can't be seen, so its fields are trusted to be the listed ones
+    options.external_convert.iter().for_each(|(external, external_fields)| {
+        let mut converted = false;
+        structs.iter().for_each(|(name, strukt)| {
+            let name = Ident::new(name, Span::call_site());
+            let fields = external_fields.iter().map(|field| strukt.field(field)).collect::<Option<Vec<_>>>();
+            if let Some(fields) = fields {
+                converted = true;
+                let setters = fields.iter().map(|field| {
+                    let (field_name, field_ident) = (field.name(), field.ident());
+                    quote! { #field_name: other.#field_ident }
+                });
+                output.push(quote! {
+                    impl From<#name> for #external {
+                        #conversion_attrs
+                        fn from(other: #name) -> Self {
+                            #external { #(#setters,)* }
+                        }
+                    }
+                });
+            }
+            if strukt.fields.iter().all(|field| field.constant.is_some() || external_fields.contains(&field.name())) {
+                converted = true;
+                let setters = strukt.fields.iter().map(|field| {
+                    let (field_name, field_ident) = (field.name(), field.ident());
+                    match &field.constant {
+                        Some(constant) => quote! { #field_ident: #constant },
+                        None => quote! { #field_ident: other.#field_name },
+                    }
+                });
+                output.push(quote! {
+                    impl From<#external> for #name {
+                        #conversion_attrs
+                        fn from(other: #external) -> Self {
+                            Self { #(#setters,)* }
+                        }
+                    }
+                });
+            }
+        });
+        if !converted {
+            panic!(
+                "`#[boilermates(external_convert(...))]` has no struct to convert from or into `{}`",
+                quote!(#external).to_string().replace(' ', "")
+            );
+        }
+    });
+
+    // `From` can't be implemented between `Result`s, since neither is local, so this is an extension trait
+    if options.result_into {
+        let vis = &main.vis;
+        let result_trait = Ident::new(&format!("{}ResultInto", main_name), Span::call_site());
+        let map_into_fn_name = options.method_name("map_into");
+        output.push(quote! {
+            #vis trait #result_trait<S, E> {
+                fn #map_into_fn_name<T: From<S>>(self) -> Result<T, E>;
             }
+        });
+        structs.keys().for_each(|name| {
+            let name = Ident::new(name, Span::call_site());
+            output.push(quote! {
+                impl<E> #result_trait<#name, E> for Result<#name, E> {
+                    #conversion_attrs
+                    fn #map_into_fn_name<T: From<#name>>(self) -> Result<T, E> {
+                        self.map(T::from)
+                    }
+                }
+            });
+        });
+    }
 
-            trait #neg_trait_name {}
-        };
+    options.into_enum_variant.iter().for_each(|(enum_path, variant_path)| {
+        let main_ident = &main.ident;
+        output.push(quote! {
+            impl From<#main_ident> for #enum_path {
+                #conversion_attrs
+                fn from(other: #main_ident) -> Self {
+                    #variant_path(other)
+                }
+            }
 
-        structs.iter_mut().for_each(|(struct_name, strukt)| {
-            let struct_ident = Ident::new(struct_name, Span::call_site());
+            impl TryFrom<#enum_path> for #main_ident {
+                type Error = #enum_path;
 
-            if add_to.contains(struct_name) {
-                strukt.fields.push(field.clone());
-                
-                traits = quote! {
-                    #traits
-                    impl #trait_name for #struct_ident {
-                        fn #field_name(&self) -> &#field_ty {
-                            &self.#field_name
-                        }
+                #conversion_attrs
+                fn try_from(other: #enum_path) -> Result<Self, Self::Error> {
+                    #[allow(unreachable_patterns)]
+                    match other {
+                        #variant_path(inner) => Ok(inner),
+                        other => Err(other),
+                    }
+                }
+            }
+        });
+    });
 
-                        fn #setter_fn(&mut self, value: #field_ty) {
-                            self.#field_name = value;
+    options.into_string_for.iter().for_each(|(name, expr)| {
+        let name = Ident::new(name, Span::call_site());
+        output.push(quote! {
+            impl From<#name> for String {
+                #conversion_attrs
+                fn from(other: #name) -> Self {
+                    impl #name {
+                        fn __boilermates_into_string(self) -> String {
+                            #expr
                         }
                     }
-                };
-            } else {
-                traits = quote! {
-                    #traits
-                    impl #neg_trait_name for #struct_ident {}
-                };
+                    other.__boilermates_into_string()
+                }
             }
-
         });
     });
 
-    let mut output = quote! {};
-    structs.iter().for_each(|(name, strukt)| {
-        let out_struct = DeriveInput {
-            attrs: strukt.attrs.clone(),
-            data: Data::Struct(DataStruct {
-                fields: Fields::Named(FieldsNamed {
-                    named: strukt
-                        .fields
-                        .iter()
-                        .cloned()
-                        .map(Into::<Field>::into)
-                        .collect(),
-                    ..fields
-                }),
-                ..data_struct
-            }),
-            ident: Ident::new(name, Span::call_site()),
-            ..main.clone()
+    options.delegate.iter().for_each(|(name, trait_path)| {
+        let [field] = structs[name].fields.as_slice() else {
+            panic!("`#[boilermates(delegate(...))]` requires `{}` to have exactly one field", name);
         };
-        output = quote! {
-            #output
-            #out_struct
+        let field_name = field.ident();
+        let field_ty = &field.field.ty;
+        let name = Ident::new(name, Span::call_site());
+        let body = match trait_path.segments.last().map(|s| s.ident.to_string()).as_deref() {
+            Some("Display" | "Debug") => quote! {
+                fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                    #trait_path::fmt(&self.#field_name, f)
+                }
+            },
+            Some("Deref") => quote! {
+                type Target = #field_ty;
+                fn deref(&self) -> &Self::Target {
+                    &self.#field_name
+                }
+            },
+            _ => panic!(
+                "`#[boilermates(delegate(...))]` supports `Display`, `Debug` and `Deref`, not `{}`",
+                quote! { #trait_path }.to_string().replace(' ', "")
+            ),
         };
+        output.push(quote! {
+            impl #trait_path for #name {
+                #body
+            }
+        });
+    });
 
-        structs.iter().for_each(|(other_name, other)| {
+    output.push(traits);
+    let mut output = quote! { #(#output)* };
 
-            if name == other_name { return }
-            let name = Ident::new(name, Span::call_site());
-            let other_name = Ident::new(other_name, Span::call_site());
-            let missing_fields = strukt.missing_fields_from(other);
-            let missing_fields_without_defaults = missing_fields
-                .iter()
-                .filter(|f| !f.default)
-                .collect::<Vec<_>>();
+    // Generated code reads every field, which would trip deprecation warnings meant for the users of a field
+    let deprecated = structs.values().flat_map(|strukt| &strukt.fields).any(|field| {
+        field.field.attrs.iter().any(|attr| attr.path.is_ident("deprecated"))
+    });
+    if deprecated {
+        let mut file = syn::parse2::<syn::File>(output).expect("Generated code should parse");
+        file.items.iter_mut().for_each(|item| match item {
+            syn::Item::Impl(item) => item.attrs.push(parse_quote!(#[allow(deprecated)])),
+            syn::Item::Fn(item) => item.attrs.push(parse_quote!(#[allow(deprecated)])),
+            _ => {}
+        });
+        output = quote! { #file };
+    }
 
-            
-            let default_field_setters = missing_fields.iter().filter(|f| f.default).fold(quote!{}, |acc, field| {
-                let field_name = field.name();
-                quote! {
-                    #acc
-                    #field_name: Default::default(),
+    if !generic_params.is_empty() {
+        let mut file = syn::parse2::<syn::File>(output).expect("Generated code should parse");
+        file.items.iter_mut().for_each(|item| add_generics(item, &main.ident, &main.generics, &options.monomorphize));
+        output = quote! { #file };
+    }
+
+    output.into()
+}
+
+#[proc_macro_attribute]
+pub fn boilermates_config(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut module = parse_macro_input!(item as syn::ItemMod);
+    let args = parse_macro_input!(attr as AttributeArgs);
+
+    let mut derives = vec![];
+    args.iter().for_each(|arg| match arg {
+        NestedMeta::Meta(syn::Meta::List(list)) if list.path.is_ident("default_derives") => {
+            list.nested.iter().for_each(|nested| match nested {
+                NestedMeta::Meta(syn::Meta::Path(path)) => derives.push(path.clone()),
+                _ => panic!("`#[boilermates_config(default_derives(...))]` expects trait paths"),
+            })
+        }
+        _ => panic!("Unknown attrbute `#[boilermates_config({})]`", quote!(#arg)),
+    });
+
+    if let Some((_, items)) = module.content.as_mut() {
+        apply_config(items, &derives);
+    }
+
+    quote!(#module).into()
+}
+
+// Proc macros can't share state between invocations, so instead of setting defaults for later
+// `#[boilermates]` invocations, the config is applied by rewriting the structs in the module
+fn apply_config(items: &mut [syn::Item], default_derives: &[syn::Path]) {
+    if default_derives.is_empty() {
+        return;
+    }
+    // The derives that the attributes don't have yet, since deriving a trait twice conflicts
+    let missing_derives = |attrs: &[Attribute]| {
+        default_derives
+            .iter()
+            .filter(|d| !d.segments.last().is_some_and(|s| derives(attrs, &s.ident.to_string())))
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+
+    items.iter_mut().for_each(|item| match item {
+        syn::Item::Struct(strukt) => {
+            let Some((index, variants)) = strukt.attrs.iter().enumerate().find_map(|(i, attr)| {
+                if !attr.path.is_ident("boilermates") {
+                    return None;
                 }
-            });
-            
-            if missing_fields_without_defaults.is_empty() {
-                let common_field_setters = strukt.same_fields_as(other).iter().fold(quote!{}, |acc, field| {
-                    let field_name = &field.name();
-                    quote! {
-                        #acc
-                        #field_name: other.#field_name,
+                let Ok(syn::Meta::List(list)) = attr.parse_meta() else { return None };
+                let variants = list
+                    .nested
+                    .iter()
+                    .map(|n| match n {
+                        NestedMeta::Lit(Lit::Str(lit)) => Some(lit.value()),
+                        _ => None,
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+                Some((i, variants))
+            }) else {
+                return;
+            };
+
+            let variant_attrs = variants
+                .iter()
+                .filter_map(|variant| {
+                    let variant_derives = missing_derives(&attrs_for(&strukt.attrs, variant));
+                    if variant_derives.is_empty() {
+                        return None;
                     }
-                });
+                    let derive_attr_str = format!("#[derive({})]", quote!(#(#variant_derives),*));
+                    Some(parse_quote!(#[boilermates(attr_for(#variant, #derive_attr_str))]))
+                })
+                .collect::<Vec<Attribute>>();
+            strukt.attrs.splice(index + 1..index + 1, variant_attrs);
+            let main_derives = missing_derives(&strukt.attrs);
+            if !main_derives.is_empty() {
+                strukt.attrs.push(parse_quote!(#[derive(#(#main_derives),*)]));
+            }
+        }
+        syn::Item::Mod(module) => {
+            if let Some((_, items)) = module.content.as_mut() {
+                apply_config(items, default_derives);
+            }
+        }
+        _ => {}
+    });
+}
 
-                output = quote! {
-                    #output
-                    impl From<#other_name> for #name {
-                        fn from(other: #other_name) -> Self {
-                            Self {
-                                #common_field_setters
-                                #default_field_setters
-                            }
+// The attributes given to `variant` with `#[boilermates(attr_for(...))]`
+fn attrs_for(attrs: &[Attribute], variant: &str) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path.is_ident("boilermates"))
+        .filter_map(|attr| match attr.parse_meta() {
+            Ok(syn::Meta::List(list)) => match list.nested.first() {
+                Some(NestedMeta::Meta(syn::Meta::List(attr_for))) if attr_for.path.is_ident("attr_for") => {
+                    match (attr_for.nested.iter().next(), attr_for.nested.iter().nth(1)) {
+                        (Some(NestedMeta::Lit(Lit::Str(strukt))), Some(NestedMeta::Lit(Lit::Str(attr))))
+                            if strukt.value() == variant =>
+                        {
+                            syn::parse::Parser::parse_str(Attribute::parse_outer, &attr.value()).ok()
                         }
+                        _ => None,
                     }
-                };
+                }
+                _ => None,
+            },
+            _ => None,
+        })
+        .flatten()
+        .collect()
+}
+
+fn unknown_directive(ident: &impl std::fmt::Display, valid: &[&str]) -> ! {
+    panic!(
+        "Unknown attribute `#[boilermates({})]`. Expected one of: {}",
+        ident,
+        valid.iter().map(|d| format!("`{}`", d)).collect::<Vec<_>>().join(", ")
+    )
+}
+
+fn rename_type(ty: &syn::Type, from: &str, to: &Ident) -> syn::Type {
+    struct RenameType<'a> {
+        from: &'a str,
+        to: &'a Ident,
+    }
+
+    impl syn::visit_mut::VisitMut for RenameType<'_> {
+        fn visit_type_path_mut(&mut self, ty: &mut syn::TypePath) {
+            if ty.qself.is_none() && ty.path.is_ident(self.from) {
+                ty.path = self.to.clone().into();
             }
-            if !missing_fields.is_empty() {
-                let common_field_setters = strukt.same_fields_as(other).iter().fold(quote!{}, |acc, field| {
-                    let field_name = field.name();
-                    quote! {
-                        #acc
-                        #field_name: self.#field_name,
-                    }
+            syn::visit_mut::visit_type_path_mut(self, ty);
+        }
+    }
+
+    let mut ty = ty.clone();
+    syn::visit_mut::VisitMut::visit_type_mut(&mut RenameType { from, to }, &mut ty);
+    ty
+}
+
+// Replaces the type parameters in `ty` with their concrete types
+fn substitute_types(ty: &syn::Type, params: &[(Ident, syn::Type)]) -> syn::Type {
+    let mut ty = ty.clone();
+    syn::visit_mut::VisitMut::visit_type_mut(&mut Monomorphize { main: None, params }, &mut ty);
+    ty
+}
+
+// Replaces the type parameters with their concrete types, and the bare path of the main struct with its full type
+struct Monomorphize<'a> {
+    main: Option<(&'a Ident, syn::Type)>,
+    params: &'a [(Ident, syn::Type)],
+}
+
+impl syn::visit_mut::VisitMut for Monomorphize<'_> {
+    fn visit_type_mut(&mut self, ty: &mut syn::Type) {
+        if let syn::Type::Path(syn::TypePath { qself: None, path }) = ty {
+            if let Some((_, main_ty)) = self.main.as_ref().filter(|(main, _)| path.is_ident(*main)) {
+                *ty = main_ty.clone();
+                return;
+            }
+            if let Some((_, concrete)) = self.params.iter().find(|(param, _)| path.is_ident(param)) {
+                *ty = concrete.clone();
+                return;
+            }
+        }
+        syn::visit_mut::visit_type_mut(self, ty);
+    }
+}
+
+// The names of the types referred to by a bare identifier in a node
+#[derive(Default)]
+struct BareTypes(Vec<String>);
+
+impl<'ast> syn::visit::Visit<'ast> for BareTypes {
+    fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+        if let (None, Some(ident)) = (&ty.qself, ty.path.get_ident()) {
+            self.0.push(ident.to_string());
+        }
+        syn::visit::visit_type_path(self, ty);
+    }
+}
+
+// The generated items refer to the main struct by its bare name. Items that involve a monomorphized variant get
+// the variant's concrete types, and the others get the generic parameters of the main struct
+fn add_generics(
+    item: &mut syn::Item,
+    main: &Ident,
+    generics: &syn::Generics,
+    monomorphize: &HashMap<String, Vec<(Ident, syn::Type)>>,
+) {
+    use syn::visit::Visit;
+    use syn::visit_mut::VisitMut;
+
+    let params = generics.type_params().map(|param| param.ident.to_string()).collect::<Vec<_>>();
+    let (_, ty_generics, _) = generics.split_for_impl();
+    let generic_ty: syn::Type = parse_quote!(#main #ty_generics);
+    let mentions_generic = |types: &BareTypes| types.0.iter().any(|ty| *main == *ty || params.contains(ty));
+    let add_params = |to: &mut syn::Generics| {
+        to.params.extend(generics.params.iter().cloned());
+        if let Some(where_clause) = &generics.where_clause {
+            to.make_where_clause().predicates.extend(where_clause.predicates.iter().cloned());
+        }
+    };
+    let mut generic = Monomorphize { main: Some((main, generic_ty)), params: &[] };
+
+    match item {
+        syn::Item::Impl(item) => {
+            let mut header = BareTypes::default();
+            header.visit_type(&item.self_ty);
+            if let Some((_, path, _)) = &item.trait_ {
+                header.visit_path(path);
+            }
+            let mut signatures = BareTypes::default();
+            item.items.iter().for_each(|impl_item| {
+                if let syn::ImplItem::Method(method) = impl_item {
+                    signatures.visit_signature(&method.sig);
+                }
+            });
+            let variant = header.0.iter().chain(&signatures.0).find_map(|ty| monomorphize.get_key_value(ty));
+            if let Some((_, concrete)) = variant {
+                let args = generics.type_params().map(|param| {
+                    concrete
+                        .iter()
+                        .find(|(p, _)| *p == param.ident)
+                        .map(|(_, ty)| ty.clone())
+                        .unwrap_or_else(|| panic!("Missing concrete type. This should never happen."))
                 });
-               
-                let into_args = missing_fields.iter().fold(quote!{}, |acc, field| {
-                    let field_name = field.name();
-                    let field_ty = &field.field.ty;
-                    quote! {
-                        #acc
-                        #field_name: #field_ty,
+                let main_ty: syn::Type = parse_quote!(#main<#(#args),*>);
+                Monomorphize { main: Some((main, main_ty)), params: concrete }.visit_item_impl_mut(item);
+            } else if mentions_generic(&header) {
+                add_params(&mut item.generics);
+                generic.visit_item_impl_mut(item);
+            } else if item.trait_.is_none() {
+                item.items.iter_mut().for_each(|impl_item| {
+                    if let syn::ImplItem::Method(method) = impl_item {
+                        let mut types = BareTypes::default();
+                        types.visit_signature(&method.sig);
+                        if mentions_generic(&types) {
+                            add_params(&mut method.sig.generics);
+                            generic.visit_impl_item_method_mut(method);
+                        }
                     }
                 });
+            }
+        }
+        syn::Item::Fn(item) => {
+            let mut types = BareTypes::default();
+            types.visit_signature(&item.sig);
+            if mentions_generic(&types) {
+                add_params(&mut item.sig.generics);
+                generic.visit_item_fn_mut(item);
+            }
+        }
+        _ => {}
+    }
+}
 
-                let into_defaults_args = missing_fields_without_defaults.iter().fold(quote!{}, |acc, field| {
-                    let field_name = field.name();
-                    let field_ty = &field.field.ty;
-                    quote! {
-                        #acc
-                        #field_name: #field_ty,
-                    }
-                });
+fn type_mentions(ty: &syn::Type, name: &str) -> bool {
+    struct Mentions<'a> {
+        name: &'a str,
+        found: bool,
+    }
 
-                let into_missing_setters = missing_fields
-                    .iter()
-                    .fold(quote! {}, |acc, field| {
-                        let field_name = field.name();
-                        quote! { #acc #field_name, }
-                    });
+    impl<'ast> syn::visit::Visit<'ast> for Mentions<'_> {
+        fn visit_type_path(&mut self, ty: &'ast syn::TypePath) {
+            self.found |= ty.qself.is_none() && ty.path.is_ident(self.name);
+            syn::visit::visit_type_path(self, ty);
+        }
+    }
 
-                let into_defaults_missing_setters = missing_fields_without_defaults
-                    .iter()
-                    .fold(quote! {}, |acc, field| {
-                        let field_name = field.name();
-                        quote! { #acc #field_name, }
-                    });
+    let mut mentions = Mentions { name, found: false };
+    syn::visit::Visit::visit_type(&mut mentions, ty);
+    mentions.found
+}
 
-                let into_defaults_fn_name = Ident::new(
-                    &pascal_to_snake(&format!("into{}_defaults", name)),
-                    Span::call_site()
-                );
-                
-                let into_fn_name = Ident::new(
-                    &pascal_to_snake(&format!("into{}", name)),
-                    Span::call_site()
-                );
+// Builds the conversion of `value` of type `ty`, where any of `names` refer to the struct being converted
+fn convert_self_ref(ty: &syn::Type, value: TokenStream2, names: &[&str]) -> Option<TokenStream2> {
+    if !names.iter().any(|name| type_mentions(ty, name)) {
+        return Some(value);
+    }
+    let syn::Type::Path(path) = ty else { return None };
+    if names.iter().any(|name| path.path.is_ident(name)) {
+        return Some(quote! { #value.into() });
+    }
+    let segment = path.path.segments.last()?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    let Some(syn::GenericArgument::Type(inner)) = args.args.first() else { return None };
+    match segment.ident.to_string().as_str() {
+        "Box" => {
+            let inner = convert_self_ref(inner, quote! { (*#value) }, names)?;
+            Some(quote! { Box::new(#inner) })
+        }
+        "Option" => {
+            let inner = convert_self_ref(inner, quote! { value }, names)?;
+            Some(quote! { #value.map(|value| #inner) })
+        }
+        "Vec" => {
+            let inner = convert_self_ref(inner, quote! { value }, names)?;
+            Some(quote! { #value.into_iter().map(|value| #inner).collect() })
+        }
+        _ => None,
+    }
+}
 
-                output = quote! {
-                    #output
-                    impl #other_name {
-                        pub fn #into_fn_name(self, #into_args) -> #name {
-                            #name {
-                                #common_field_setters
-                                #into_missing_setters
-                            }
-                        }
+// Whether `attrs` include a derive of a trait named `name`
+fn derives(attrs: &[Attribute], name: &str) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident("derive")
+            && matches!(attr.parse_meta(), Ok(syn::Meta::List(list)) if list.nested.iter().any(|n| matches!(
+                n,
+                NestedMeta::Meta(syn::Meta::Path(path)) if path.segments.last().is_some_and(|s| s.ident == name)
+            )))
+    })
+}
 
-                        pub fn #into_defaults_fn_name(self, #into_defaults_args) -> #name {
-                            #name {
-                                #common_field_setters
-                                #default_field_setters
-                                #into_defaults_missing_setters
-                            }
-                        }
-                    }
-                };
+// The path of a tuple struct type in expression position, where generic arguments need a turbofish
+fn constructor_path(ty: &syn::Type) -> syn::Path {
+    let syn::Type::Path(path) = ty else { panic!("Expected a tuple struct type. This should never happen.") };
+    let mut path = path.path.clone();
+    path.segments.iter_mut().for_each(|segment| {
+        if let syn::PathArguments::AngleBracketed(args) = &mut segment.arguments {
+            args.colon2_token = Some(Default::default());
+        }
+    });
+    path
+}
+
+fn serde_flatten(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident("serde")
+            && matches!(attr.parse_meta(), Ok(syn::Meta::List(list)) if list.nested.iter().any(|n| matches!(
+                n,
+                NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("flatten")
+            )))
+    })
+}
+
+fn repr_c(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        attr.path.is_ident("repr")
+            && matches!(attr.parse_meta(), Ok(syn::Meta::List(list)) if list.nested.iter().any(|n| matches!(
+                n,
+                NestedMeta::Meta(syn::Meta::Path(path)) if path.is_ident("C")
+            )))
+    })
+}
+
+// Proc macros can't emit warnings on stable, so this uses a deprecated item to get one
+fn warning(message: &str, span: Span) -> TokenStream2 {
+    let usage = quote_spanned! { span=> boilermates_warning };
+    quote! {
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const boilermates_warning: () = ();
+            #usage
+        };
+    }
+}
+
+fn is_known_non_copy(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else { return false };
+    path.path.segments.last().is_some_and(|segment| {
+        [
+            "String", "Vec", "Box", "HashMap", "HashSet", "BTreeMap", "BTreeSet", "VecDeque",
+            "Rc", "Arc", "RefCell", "Mutex", "PathBuf", "OsString",
+        ]
+        .iter()
+        .any(|name| segment.ident == name)
+    })
+}
+
+fn is_option(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else { return false };
+    path.path.segments.last().is_some_and(|segment| segment.ident == "Option")
+}
+
+// The length of an array type, if it's a literal
+fn array_len(ty: &syn::Type) -> Option<usize> {
+    let syn::Type::Array(array) = ty else { return None };
+    let syn::Expr::Lit(syn::ExprLit { lit: Lit::Int(len), .. }) = &array.len else { return None };
+    len.base10_parse().ok()
+}
+
+// The `T` of a `Box<T>`, `Rc<T>` or `Arc<T>`
+fn pointee(ty: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last().filter(|segment| ["Box", "Rc", "Arc"].iter().any(|p| segment.ident == p))?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first() {
+        Some(syn::GenericArgument::Type(ty)) if args.args.len() == 1 => Some(ty.clone()),
+        _ => None,
+    }
+}
+
+// Whether values of `ty` can be clamped through `i128`
+fn is_clampable(ty: &syn::Type) -> bool {
+    let syn::Type::Path(path) = ty else { return false };
+    path.path.get_ident().is_some_and(|ident| {
+        ["i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "usize"].iter().any(|int| ident == int)
+    })
+}
+
+// The `T` of a `Vec<T>`
+fn vec_inner(ty: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Vec")?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first() {
+        Some(syn::GenericArgument::Type(ty)) if args.args.len() == 1 => Some(ty.clone()),
+        _ => None,
+    }
+}
+
+// The `T` of an `Option<T>`
+fn option_inner(ty: &syn::Type) -> Option<syn::Type> {
+    let syn::Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Option")?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first() {
+        Some(syn::GenericArgument::Type(ty)) if args.args.len() == 1 => Some(ty.clone()),
+        _ => None,
+    }
+}
+
+// Whether `outer` is `Option<inner>`
+fn wraps(outer: &syn::Type, inner: &syn::Type) -> bool {
+    option_inner(outer).is_some_and(|ty| quote!(#ty).to_string() == quote!(#inner).to_string())
+}
+
+// The `T` and `E` of a `Result<T, E>`
+fn result_types(ty: &syn::Type) -> Option<(syn::Type, syn::Type)> {
+    let syn::Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last().filter(|segment| segment.ident == "Result")?;
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    let mut types = args.args.iter().filter_map(|arg| match arg {
+        syn::GenericArgument::Type(ty) => Some(ty.clone()),
+        _ => None,
+    });
+    match (types.next(), types.next(), types.next()) {
+        (Some(ok), Some(error), None) => Some((ok, error)),
+        _ => None,
+    }
+}
+
+// The type part of a `#[prost(...)]` field attribute
+fn prost_kind(ty: &syn::Type) -> TokenStream2 {
+    fn scalar(ty: &syn::Type) -> TokenStream2 {
+        let syn::Type::Path(path) = ty else { return quote! { message } };
+        match path.path.get_ident().map(ToString::to_string).as_deref() {
+            Some("u32") => quote! { uint32 },
+            Some("u64") => quote! { uint64 },
+            Some("i32") => quote! { int32 },
+            Some("i64") => quote! { int64 },
+            Some("f32") => quote! { float },
+            Some("f64") => quote! { double },
+            Some("bool") => quote! { bool },
+            Some("String") => quote! { string },
+            _ => quote! { message },
+        }
+    }
+
+    let syn::Type::Path(path) = ty else { return scalar(ty) };
+    let Some(segment) = path.path.segments.last() else { return scalar(ty) };
+    let syn::PathArguments::AngleBracketed(args) = &segment.arguments else { return scalar(ty) };
+    let Some(syn::GenericArgument::Type(inner)) = args.args.first() else { return scalar(ty) };
+    match segment.ident.to_string().as_str() {
+        "Vec" if quote!(#inner).to_string() == "u8" => quote! { bytes = "vec" },
+        "Vec" => {
+            let inner = scalar(inner);
+            quote! { #inner, repeated }
+        }
+        "Option" => {
+            let inner = scalar(inner);
+            quote! { #inner, optional }
+        }
+        _ => scalar(ty),
+    }
+}
+
+// The fields accessed through `self.field` in `expr`, including inside macro arguments like `format!`
+fn self_fields(expr: &syn::Expr) -> Vec<Ident> {
+    struct SelfFields(Vec<Ident>);
+
+    impl<'ast> syn::visit::Visit<'ast> for SelfFields {
+        fn visit_expr_field(&mut self, expr: &'ast syn::ExprField) {
+            if let (syn::Expr::Path(base), syn::Member::Named(field)) = (&*expr.base, &expr.member) {
+                if base.path.is_ident("self") {
+                    self.0.push(field.clone());
+                }
             }
+            syn::visit::visit_expr_field(self, expr);
+        }
 
-        })
+        fn visit_macro(&mut self, mac: &'ast syn::Macro) {
+            let args = mac.parse_body_with(
+                syn::punctuated::Punctuated::<syn::Expr, syn::Token![,]>::parse_terminated,
+            );
+            if let Ok(args) = args {
+                args.iter().for_each(|arg| self.visit_expr(arg));
+            }
+        }
+    }
+
+    let mut fields = SelfFields(vec![]);
+    syn::visit::Visit::visit_expr(&mut fields, expr);
+    fields.0
+}
+
+// A markdown table of which fields each struct has, with the main struct first
+fn doc_matrix(structs: &HashMap<String, Struct>, main_name: &str) -> String {
+    let mut names = structs.keys().filter(|n| *n != main_name).collect::<Vec<_>>();
+    names.sort();
+    let columns = std::iter::once(&structs[main_name])
+        .chain(names.iter().map(|n| &structs[*n]))
+        .collect::<Vec<_>>();
+    let rows = columns.iter().flat_map(|s| s.fields.iter()).fold(vec![], |mut acc, field| {
+        if !acc.contains(&field.name()) { acc.push(field.name()) }
+        acc
     });
 
-    output = quote! {
-        #output
-        #traits
-    };
+    let mut doc = format!("\n| Field | `{}` |", main_name);
+    names.iter().for_each(|n| doc.push_str(&format!(" `{}` |", n)));
+    doc.push_str(&format!("\n|---|{}", "---|".repeat(columns.len())));
+    rows.iter().for_each(|row| {
+        doc.push_str(&format!("\n| `{}` |", row));
+        columns.iter().for_each(|strukt| {
+            let cell = match strukt.fields.iter().find(|f| f.name() == *row) {
+                Some(f) if f.ident() == *row => " ✓ |".to_owned(),
+                Some(f) => format!(" `{}` |", f.ident()),
+                None => " |".to_owned(),
+            };
+            doc.push_str(&cell);
+        });
+    });
+    doc
+}
 
-    output.into()
+// A description of the structs, their fields and the conversions that were generated between them, in a stable order
+fn spec(structs: &HashMap<String, Struct>, conversions: &HashMap<(String, String), Vec<String>>) -> String {
+    let mut names = structs.keys().collect::<Vec<_>>();
+    names.sort();
+    let type_name = |ty: &syn::Type| quote!(#ty).to_string().replace(' ', "");
+    names.iter().fold(String::new(), |mut spec, name| {
+        let strukt = &structs[*name];
+        spec.push_str(&format!("struct {}\n", name));
+        strukt.fields.iter().for_each(|field| {
+            spec.push_str(&format!("  field {}: {}", field.ident(), type_name(&field.field.ty)));
+            if field.default { spec.push_str(" (default)") }
+            if field.derived.is_some() { spec.push_str(" (derived)") }
+            spec.push('\n');
+        });
+        names.iter().filter(|other| *other != name).for_each(|other| {
+            let conversion = conversions
+                .get(&(name.to_string(), other.to_string()))
+                .map_or_else(|| "none".to_owned(), |conversions| conversions.join(", "));
+            spec.push_str(&format!("  from {}: {}\n", other, conversion));
+        });
+        spec
+    })
 }
 
 fn pascal_to_snake(s: &str) -> String {
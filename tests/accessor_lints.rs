@@ -0,0 +1,28 @@
+//! The generated accessors must not trip the Clippy lints of the `pedantic`, `nursery` and `restriction` groups
+//! that they allow, which only `cargo clippy --all-targets` checks.
+#![deny(clippy::pedantic, clippy::nursery, clippy::missing_docs_in_private_items)]
+
+use boilermates::boilermates;
+
+/// A user with a field that's missing from `PublicUser`
+#[boilermates("PublicUser")]
+pub struct User {
+    /// Small enough to be passed by value
+    pub id: u8,
+    /// Only in `User`
+    #[boilermates(not_in("PublicUser"))]
+    pub password: String,
+}
+
+/// Reads the id of any struct that has one
+fn id_of(user: &impl HasId) -> u8 {
+    *user.id()
+}
+
+#[test]
+fn accessors() {
+    assert_eq!(id_of(&User { id: 1, password: String::new() }), 1);
+
+    let user = PublicUser { id: 2 }.into_user("hunter2".to_owned());
+    assert_eq!((id_of(&user), user.password()), (2, &"hunter2".to_owned()));
+}
@@ -0,0 +1,187 @@
+use boilermates::boilermates;
+
+pub struct Address {
+    pub street: String,
+    pub city: String,
+}
+
+// Fields are looked up by their name in the main struct, which `strip_prefix` changes in `UserDto`, and derived
+// and computed fields are found through the fields they're derived from
+#[boilermates("UserDto", "FlatUser")]
+#[boilermates(strip_prefix("UserDto", "user_"))]
+#[boilermates(derive_field("UserDto", "display_name", "User::display_name"))]
+#[boilermates(dry_run)]
+pub struct User {
+    pub id: u64,
+    pub user_name: String,
+    #[boilermates(flatten_from("FlatUser", inner_fields(street = "String", city = "String")))]
+    pub address: Address,
+    #[boilermates(only_in("UserDto"))]
+    pub display_name: String,
+    #[boilermates(not_in("User"))]
+    #[boilermates(default)]
+    pub tags: Vec<String>,
+}
+
+impl User {
+    fn display_name(&self) -> String {
+        self.user_name.to_uppercase()
+    }
+}
+
+fn user() -> User {
+    User {
+        id: 1,
+        user_name: "alice".to_owned(),
+        address: Address { street: "1 Main St".to_owned(), city: "Springfield".to_owned() },
+    }
+}
+
+#[test]
+fn matches_fields_by_name() {
+    assert_eq!(
+        User::BOILERMATES_SPEC,
+        "\
+struct FlatUser
+  field id: u64
+  field user_name: String
+  field street: String (derived)
+  field city: String (derived)
+  field tags: Vec<String> (default)
+  from User: From, into_flat_user(tags: Vec<String>)
+  from UserDto: From
+struct User
+  field id: u64
+  field user_name: String
+  field address: Address
+  from FlatUser: into_user(address: Address)
+  from UserDto: From
+struct UserDto
+  field id: u64
+  field name: String
+  field address: Address
+  field display_name: String
+  field tags: Vec<String> (default)
+  from FlatUser: into_user_dto(address: Address, display_name: String)
+  from User: From, into_user_dto(tags: Vec<String>)
+"
+    );
+
+    let dto = UserDto::from(user());
+    assert_eq!((dto.id, dto.name.as_str(), dto.display_name.as_str()), (1, "alice", "ALICE"));
+    let flat = FlatUser::from(user().into_user_dto(vec!["admin".to_owned()]));
+    assert_eq!((flat.street.as_str(), flat.city.as_str(), flat.tags.as_slice()), ("1 Main St", "Springfield", &["admin".to_owned()][..]));
+    let user = User::from(UserDto::from(user()));
+    assert_eq!((user.user_name.as_str(), user.address.street.as_str()), ("alice", "1 Main St"));
+}
+
+#[boilermates("PublicAuthor")]
+pub struct Author {
+    pub name: String,
+    #[boilermates(not_in("PublicAuthor"))]
+    pub email: String,
+}
+
+// Truncated and nested fields only convert one way, so the fields a pair shares depend on the direction
+#[boilermates("Summary")]
+#[boilermates(truncate_array_in("Summary", "scores", 2))]
+#[boilermates(nested_convert("Summary", "author", "PublicAuthor"))]
+#[boilermates(dry_run)]
+pub struct Post {
+    pub title: String,
+    pub scores: [u8; 4],
+    pub author: Author,
+}
+
+#[test]
+fn matches_fields_per_direction() {
+    assert_eq!(
+        Post::BOILERMATES_SPEC,
+        "\
+struct Post
+  field title: String
+  field scores: [u8;4]
+  field author: Author
+  from Summary: into_post(scores: [u8;4], author: Author)
+struct Summary
+  field title: String
+  field scores: [u8;2]
+  field author: PublicAuthor
+  from Post: From
+"
+    );
+
+    let author = Author { name: "alice".to_owned(), email: "alice@example.com".to_owned() };
+    let summary = Summary::from(Post { title: "Hello".to_owned(), scores: [1, 2, 3, 4], author });
+    assert_eq!((summary.title.as_str(), summary.scores, summary.author.name.as_str()), ("Hello", [1, 2], "alice"));
+
+    let author = Author { name: "bob".to_owned(), email: "bob@example.com".to_owned() };
+    let post = summary.into_post([5, 6, 7, 8], author);
+    assert_eq!((post.title.as_str(), post.scores, post.author.email.as_str()), ("Hello", [5, 6, 7, 8], "bob@example.com"));
+}
+
+// Each kind of item the macro generates is pushed into the output separately, so they're all used here. It's in a
+// module since the `Has` traits are named after fields that the structs above have too
+mod item {
+    use boilermates::boilermates;
+
+    #[boilermates("NewItem", "ItemSummary")]
+    #[boilermates(builder)]
+    #[boilermates(field_enum)]
+    #[boilermates(iter_convert)]
+    #[boilermates(dry_run)]
+    pub struct Item {
+        #[boilermates(not_in("NewItem"))]
+        pub id: u32,
+        pub name: String,
+        #[boilermates(not_in("ItemSummary"))]
+        #[boilermates(default)]
+        pub tags: Vec<String>,
+    }
+
+    // The `Has` traits are private to the module they're generated in
+    pub fn id_and_name(item: &Item) -> (u32, &str) {
+        (*item.id(), item.name())
+    }
+
+    pub fn without_tags(summary: &ItemSummary) {
+        fn check(_: &impl HasNoTags) {}
+        check(summary);
+    }
+}
+
+#[test]
+fn generates_every_item() {
+    use item::*;
+
+    assert_eq!(
+        Item::BOILERMATES_SPEC,
+        "\
+struct Item
+  field id: u32
+  field name: String
+  field tags: Vec<String> (default)
+  from ItemSummary: From, into_item(tags: Vec<String>)
+  from NewItem: into_item(id: u32)
+struct ItemSummary
+  field id: u32
+  field name: String
+  from Item: From
+  from NewItem: into_item_summary(id: u32)
+struct NewItem
+  field name: String
+  field tags: Vec<String> (default)
+  from Item: From
+  from ItemSummary: From, into_new_item(tags: Vec<String>)
+"
+    );
+
+    let item = NewItem::builder().name("lamp".to_owned()).build().unwrap().into_item(7);
+    assert_eq!((id_and_name(&item), item.tags.len()), ((7, "lamp"), 0));
+    assert_eq!(item.get(ItemField::Name).downcast_ref::<String>().unwrap(), "lamp");
+    without_tags(&ItemSummary::from(item));
+
+    let summaries = ItemSummary::convert_iter([Item { id: 1, name: "a".to_owned(), tags: vec![] }]).collect::<Vec<_>>();
+    assert_eq!(summaries.iter().map(|summary| summary.id).collect::<Vec<_>>(), [1]);
+    assert_eq!(NewItemField::ALL.len(), 2);
+}
@@ -0,0 +1,10 @@
+use boilermates::boilermates;
+
+#[boilermates("Small")]
+#[boilermates(attr_for("Small", "#[derive(Clone, Copy)]"))]
+struct User {
+    id: u64,
+    name: String,
+}
+
+fn main() {}